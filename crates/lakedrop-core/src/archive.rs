@@ -0,0 +1,232 @@
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use flate2::read::GzDecoder;
+use zip::ZipArchive;
+
+use crate::http::fnv1a;
+use crate::{detect_kind_from_name, FileKind};
+
+/// A member is listed when its own name maps to a loadable format; nested
+/// archives and members with unsafe paths are skipped.
+fn is_data_member(name: &str) -> bool {
+    !name.ends_with('/')
+        && is_safe_member(name)
+        && detect_kind_from_name(Path::new(name)).is_ok_and(|spec| spec.kind != FileKind::Archive)
+}
+
+/// Member paths must stay inside the extraction directory and map to it one
+/// to one, so absolute paths and empty, `.` or `..` segments are refused
+/// rather than normalized (`a/../b.csv` would otherwise share `b.csv`'s file).
+fn is_safe_member(name: &str) -> bool {
+    name.split(['/', '\\'])
+        .all(|part| !part.is_empty() && part != "." && part != "..")
+}
+
+fn open_tar(path: &Path, compressed: bool) -> Result<tar::Archive<Box<dyn Read>>, String> {
+    let file = BufReader::new(File::open(path).map_err(|err| err.to_string())?);
+    let reader: Box<dyn Read> = if compressed {
+        Box::new(GzDecoder::new(file))
+    } else {
+        Box::new(file)
+    };
+    Ok(tar::Archive::new(reader))
+}
+
+/// Lists the loadable members of a ZIP or (gzipped) tar archive without
+/// decompressing their contents.
+pub(crate) fn list_members(
+    path: &Path,
+    extension: &str,
+    compressed: bool,
+) -> Result<Vec<String>, String> {
+    let mut members = Vec::new();
+    if extension == "zip" {
+        let file = File::open(path).map_err(|err| err.to_string())?;
+        let archive = ZipArchive::new(file).map_err(|err| err.to_string())?;
+        members.extend(
+            archive
+                .file_names()
+                .filter(|name| is_data_member(name))
+                .map(str::to_string),
+        );
+    } else {
+        let mut archive = open_tar(path, compressed)?;
+        for entry in archive.entries().map_err(|err| err.to_string())? {
+            let entry = entry.map_err(|err| err.to_string())?;
+            if !entry.header().entry_type().is_file() {
+                continue;
+            }
+            let name = entry
+                .path()
+                .map_err(|err| err.to_string())?
+                .to_string_lossy()
+                .to_string();
+            if is_data_member(&name) {
+                members.push(name);
+            }
+        }
+    }
+    members.sort();
+    Ok(members)
+}
+
+/// The cache directory for one version of an archive. The key includes the
+/// size and the modification time to the nanosecond, so an archive rewritten
+/// in place gets a fresh directory even within the same second.
+fn extract_dir(path: &Path) -> Result<PathBuf, String> {
+    let meta = std::fs::metadata(path).map_err(|err| err.to_string())?;
+    let modified = meta
+        .modified()
+        .ok()
+        .and_then(|time| time.duration_since(SystemTime::UNIX_EPOCH).ok())
+        .map(|elapsed| elapsed.as_nanos())
+        .unwrap_or(0);
+    let key = format!("{}:{}:{modified}", path.display(), meta.len());
+    let dir = std::env::temp_dir()
+        .join("lakedrop-archives")
+        .join(format!("{:016x}", fnv1a(&key)));
+    std::fs::create_dir_all(&dir).map_err(|err| err.to_string())?;
+    Ok(dir)
+}
+
+/// Where a member is extracted to inside the extraction directory.
+fn member_target(dir: &Path, member: &str) -> Result<PathBuf, String> {
+    if !is_safe_member(member) {
+        return Err(format!("Archive member has an unsafe path: {member}"));
+    }
+    Ok(member
+        .split(['/', '\\'])
+        .fold(dir.to_path_buf(), |path, part| path.join(part)))
+}
+
+/// Streams a single archive member to a temporary file so the regular loaders
+/// (including lazy Parquet/CSV scans) can read it. Extracted members are
+/// reused until the archive itself changes.
+pub(crate) fn extract_member(
+    path: &Path,
+    extension: &str,
+    compressed: bool,
+    member: &str,
+) -> Result<PathBuf, String> {
+    let target = member_target(&extract_dir(path)?, member)?;
+    if target.exists() {
+        return Ok(target);
+    }
+    if let Some(parent) = target.parent() {
+        std::fs::create_dir_all(parent).map_err(|err| err.to_string())?;
+    }
+
+    let mut partial = target.clone().into_os_string();
+    partial.push(".part");
+    let partial = PathBuf::from(partial);
+    let mut output = File::create(&partial).map_err(|err| err.to_string())?;
+    if extension == "zip" {
+        let file = File::open(path).map_err(|err| err.to_string())?;
+        let mut archive = ZipArchive::new(file).map_err(|err| err.to_string())?;
+        let mut entry = archive.by_name(member).map_err(|err| err.to_string())?;
+        io::copy(&mut entry, &mut output).map_err(|err| err.to_string())?;
+    } else {
+        let mut archive = open_tar(path, compressed)?;
+        let mut found = false;
+        for entry in archive.entries().map_err(|err| err.to_string())? {
+            let mut entry = entry.map_err(|err| err.to_string())?;
+            let name = entry
+                .path()
+                .map_err(|err| err.to_string())?
+                .to_string_lossy()
+                .to_string();
+            if name == member {
+                io::copy(&mut entry, &mut output).map_err(|err| err.to_string())?;
+                found = true;
+                break;
+            }
+        }
+        if !found {
+            return Err(format!("Archive member not found: {member}"));
+        }
+    }
+    drop(output);
+    std::fs::rename(&partial, &target).map_err(|err| err.to_string())?;
+    Ok(target)
+}
+
+/// Turns a member path into a SQL-friendly table name, e.g.
+/// `exports/Sales 2024.csv` becomes `sales_2024`.
+pub(crate) fn table_name(member: &str, taken: &[String]) -> String {
+    let stem = Path::new(member)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .and_then(|name| name.split('.').next())
+        .unwrap_or("member");
+    let mut name = stem
+        .chars()
+        .map(|ch| {
+            if ch.is_ascii_alphanumeric() {
+                ch.to_ascii_lowercase()
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>();
+    if name.is_empty() || name.starts_with(|ch: char| ch.is_ascii_digit()) {
+        name.insert(0, 't');
+        name.insert(1, '_');
+    }
+    if name == "source" {
+        name.push_str("_1");
+    }
+    let base = name.clone();
+    let mut suffix = 2;
+    while taken.contains(&name) {
+        name = format!("{base}_{suffix}");
+        suffix += 1;
+    }
+    name
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lists_loadable_members_only() {
+        assert!(is_data_member("exports/sales.csv"));
+        assert!(is_data_member("events.jsonl.gz"));
+        assert!(is_data_member("app.db"));
+        assert!(!is_data_member("exports/"));
+        assert!(!is_data_member("README.md"));
+        assert!(!is_data_member("nested.zip"));
+        assert!(!is_data_member("../sales.csv"));
+    }
+
+    #[test]
+    fn refuses_members_outside_the_extraction_dir() {
+        let dir = Path::new("cache");
+        assert_eq!(
+            member_target(dir, "exports/sales.csv").unwrap(),
+            dir.join("exports").join("sales.csv")
+        );
+        for member in [
+            "a/../b.csv",
+            "./b.csv",
+            "/etc/b.csv",
+            "a//b.csv",
+            "..\\b.csv",
+        ] {
+            assert!(member_target(dir, member).is_err(), "{member}");
+        }
+    }
+
+    #[test]
+    fn table_names_are_sql_friendly_and_unique() {
+        assert_eq!(table_name("exports/Sales 2024.csv", &[]), "sales_2024");
+        assert_eq!(table_name("2024.parquet", &[]), "t_2024");
+        assert_eq!(table_name("source.csv", &[]), "source_1");
+        assert_eq!(table_name("events.jsonl.gz", &[]), "events");
+        let taken = vec!["sales".to_string(), "sales_2".to_string()];
+        assert_eq!(table_name("a/sales.csv", &taken), "sales_3");
+    }
+}
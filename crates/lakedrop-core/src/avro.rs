@@ -0,0 +1,455 @@
+//! Avro object container files. The embedded writer schema decides the
+//! Polars types: `["null", T]` unions become a nullable `T`, records become
+//! structs, arrays and maps become lists, and date, time and timestamp
+//! logical types become their temporal counterparts, with `timestamp-*`
+//! instants in UTC and `local-timestamp-*` without a zone.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+use apache_avro::schema::{Name, Schema};
+use apache_avro::types::Value as AvroValue;
+use apache_avro::Reader;
+use polars::prelude::*;
+
+fn utc(unit: TimeUnit) -> DataType {
+    DataType::Datetime(unit, Some("UTC".to_string()))
+}
+
+/// How values of one Avro schema turn into Polars values.
+#[derive(Clone)]
+struct Decoder {
+    dtype: DataType,
+    kind: Kind,
+}
+
+#[derive(Clone)]
+enum Kind {
+    Scalar,
+    /// Unscaled big-endian bytes, divided by `10^scale`.
+    Decimal(usize),
+    List(Box<Decoder>),
+    /// Entries as `{key, value}` structs, sorted by key.
+    Map(Box<Decoder>),
+    Record(Vec<Decoder>),
+    /// Unions of several types and anything else without a Polars
+    /// counterpart, kept as text: strings as they are, the rest as JSON.
+    Json,
+}
+
+/// Builds decoders, remembering named types so later references resolve.
+#[derive(Default)]
+struct Decoders {
+    named: HashMap<Name, Decoder>,
+}
+
+impl Decoders {
+    fn scalar(dtype: DataType) -> Decoder {
+        Decoder {
+            dtype,
+            kind: Kind::Scalar,
+        }
+    }
+
+    fn build(&mut self, schema: &Schema) -> Result<Decoder, String> {
+        let decoder = match schema {
+            Schema::Null => Self::scalar(DataType::Null),
+            Schema::Boolean => Self::scalar(DataType::Boolean),
+            Schema::Int => Self::scalar(DataType::Int32),
+            Schema::Long => Self::scalar(DataType::Int64),
+            Schema::Float => Self::scalar(DataType::Float32),
+            Schema::Double => Self::scalar(DataType::Float64),
+            Schema::Bytes | Schema::Fixed(_) => Self::scalar(DataType::Binary),
+            Schema::String | Schema::Enum(_) | Schema::Uuid => Self::scalar(DataType::String),
+            Schema::Date => Self::scalar(DataType::Date),
+            Schema::TimeMillis | Schema::TimeMicros => Self::scalar(DataType::Time),
+            // `timestamp-*` are instants; `local-timestamp-*` have no zone.
+            Schema::TimestampMillis => Self::scalar(utc(TimeUnit::Milliseconds)),
+            Schema::TimestampMicros => Self::scalar(utc(TimeUnit::Microseconds)),
+            Schema::LocalTimestampMillis => {
+                Self::scalar(DataType::Datetime(TimeUnit::Milliseconds, None))
+            }
+            Schema::LocalTimestampMicros => {
+                Self::scalar(DataType::Datetime(TimeUnit::Microseconds, None))
+            }
+            Schema::Decimal(decimal) => Decoder {
+                dtype: DataType::Float64,
+                kind: Kind::Decimal(decimal.scale),
+            },
+            Schema::Array(items) => {
+                let items = self.build(items)?;
+                Decoder {
+                    dtype: DataType::List(Box::new(items.dtype.clone())),
+                    kind: Kind::List(Box::new(items)),
+                }
+            }
+            Schema::Map(values) => {
+                let values = self.build(values)?;
+                let entry = DataType::Struct(vec![
+                    Field::new("key", DataType::String),
+                    Field::new("value", values.dtype.clone()),
+                ]);
+                Decoder {
+                    dtype: DataType::List(Box::new(entry)),
+                    kind: Kind::Map(Box::new(values)),
+                }
+            }
+            Schema::Union(union) => {
+                let mut branches = union
+                    .variants()
+                    .iter()
+                    .filter(|branch| **branch != Schema::Null);
+                match (branches.next(), branches.next()) {
+                    (Some(branch), None) => self.build(branch)?,
+                    (None, _) => Self::scalar(DataType::Null),
+                    _ => Decoder {
+                        dtype: DataType::String,
+                        kind: Kind::Json,
+                    },
+                }
+            }
+            Schema::Record(record) => {
+                let mut fields = Vec::with_capacity(record.fields.len());
+                let mut decoders = Vec::with_capacity(record.fields.len());
+                for field in &record.fields {
+                    let decoder = self.build(&field.schema)?;
+                    fields.push(Field::new(&field.name, decoder.dtype.clone()));
+                    decoders.push(decoder);
+                }
+                Decoder {
+                    dtype: DataType::Struct(fields),
+                    kind: Kind::Record(decoders),
+                }
+            }
+            Schema::Ref { name } => self
+                .named
+                .get(name)
+                .cloned()
+                .ok_or_else(|| format!("Recursive Avro type {} is not supported", name.name))?,
+            _ => Decoder {
+                dtype: DataType::String,
+                kind: Kind::Json,
+            },
+        };
+
+        let name = match schema {
+            Schema::Record(record) => Some(&record.name),
+            Schema::Enum(schema) => Some(&schema.name),
+            Schema::Fixed(schema) => Some(&schema.name),
+            _ => None,
+        };
+        if let Some(name) = name {
+            self.named.insert(name.clone(), decoder.clone());
+        }
+        Ok(decoder)
+    }
+}
+
+fn mismatch() -> String {
+    "Avro value does not match its schema".to_string()
+}
+
+fn decimal_value(decimal: &apache_avro::Decimal, scale: usize) -> Result<f64, String> {
+    let bytes = Vec::<u8>::try_from(decimal).map_err(|err| err.to_string())?;
+    if bytes.len() > 16 {
+        return Err("Avro decimals wider than 128 bits are not supported".to_string());
+    }
+    let negative = bytes.first().is_some_and(|byte| byte & 0x80 != 0);
+    let mut buffer = [if negative { 0xff } else { 0 }; 16];
+    buffer[16 - bytes.len()..].copy_from_slice(&bytes);
+    Ok(i128::from_be_bytes(buffer) as f64 / 10f64.powi(scale as i32))
+}
+
+/// Datetimes carry no zone here; the column dtype adds UTC for instants.
+fn scalar_value(value: AvroValue) -> Result<AnyValue<'static>, String> {
+    let value = match value {
+        AvroValue::Boolean(value) => AnyValue::Boolean(value),
+        AvroValue::Int(value) => AnyValue::Int32(value),
+        AvroValue::Long(value) => AnyValue::Int64(value),
+        AvroValue::Float(value) => AnyValue::Float32(value),
+        AvroValue::Double(value) => AnyValue::Float64(value),
+        AvroValue::Bytes(value) | AvroValue::Fixed(_, value) => AnyValue::BinaryOwned(value),
+        AvroValue::String(value) | AvroValue::Enum(_, value) => AnyValue::StringOwned(value.into()),
+        AvroValue::Uuid(value) => AnyValue::StringOwned(value.to_string().into()),
+        AvroValue::Date(days) => AnyValue::Date(days),
+        AvroValue::TimeMillis(millis) => AnyValue::Time(millis as i64 * 1_000_000),
+        AvroValue::TimeMicros(micros) => AnyValue::Time(micros * 1_000),
+        AvroValue::TimestampMillis(millis) | AvroValue::LocalTimestampMillis(millis) => {
+            AnyValue::Datetime(millis, TimeUnit::Milliseconds, &None)
+        }
+        AvroValue::TimestampMicros(micros) | AvroValue::LocalTimestampMicros(micros) => {
+            AnyValue::Datetime(micros, TimeUnit::Microseconds, &None)
+        }
+        _ => return Err(mismatch()),
+    };
+    Ok(value)
+}
+
+fn struct_fields(dtype: &DataType) -> Vec<Field> {
+    match dtype {
+        DataType::Struct(fields) => fields.clone(),
+        _ => Vec::new(),
+    }
+}
+
+fn series(name: &str, values: &[AnyValue], dtype: &DataType) -> Result<Series, String> {
+    Series::from_any_values_and_dtype(name, values, dtype, false).map_err(|err| err.to_string())
+}
+
+impl Decoder {
+    fn value(&self, value: AvroValue) -> Result<AnyValue<'static>, String> {
+        let value = match value {
+            AvroValue::Union(_, inner) => *inner,
+            value => value,
+        };
+        if value == AvroValue::Null {
+            return Ok(AnyValue::Null);
+        }
+        match &self.kind {
+            Kind::Scalar => scalar_value(value),
+            Kind::Decimal(scale) => match value {
+                AvroValue::Decimal(decimal) => {
+                    Ok(AnyValue::Float64(decimal_value(&decimal, *scale)?))
+                }
+                _ => Err(mismatch()),
+            },
+            Kind::List(items) => {
+                let AvroValue::Array(values) = value else {
+                    return Err(mismatch());
+                };
+                let values = values
+                    .into_iter()
+                    .map(|value| items.value(value))
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(AnyValue::List(series("", &values, &items.dtype)?))
+            }
+            Kind::Map(values) => {
+                let AvroValue::Map(entries) = value else {
+                    return Err(mismatch());
+                };
+                let DataType::List(entry) = &self.dtype else {
+                    return Err(mismatch());
+                };
+                let fields = struct_fields(entry);
+                let mut entries = entries.into_iter().collect::<Vec<_>>();
+                entries.sort_by(|a, b| a.0.cmp(&b.0));
+                let entries = entries
+                    .into_iter()
+                    .map(|(key, value)| {
+                        let row = vec![AnyValue::StringOwned(key.into()), values.value(value)?];
+                        Ok(AnyValue::StructOwned(Box::new((row, fields.clone()))))
+                    })
+                    .collect::<Result<Vec<_>, String>>()?;
+                Ok(AnyValue::List(series("", &entries, entry)?))
+            }
+            Kind::Record(decoders) => {
+                let AvroValue::Record(fields) = value else {
+                    return Err(mismatch());
+                };
+                let row = fields
+                    .into_iter()
+                    .zip(decoders)
+                    .map(|((_, value), decoder)| decoder.value(value))
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(AnyValue::StructOwned(Box::new((
+                    row,
+                    struct_fields(&self.dtype),
+                ))))
+            }
+            Kind::Json => match value {
+                AvroValue::String(text) => Ok(AnyValue::StringOwned(text.into())),
+                value => {
+                    let json = serde_json::Value::try_from(value).map_err(|err| err.to_string())?;
+                    Ok(AnyValue::StringOwned(json.to_string().into()))
+                }
+            },
+        }
+    }
+}
+
+/// Reads a whole object container file. A top-level record becomes one
+/// column per field; any other top-level type becomes a single `value`
+/// column.
+pub(crate) fn read_avro(reader: impl Read) -> Result<DataFrame, String> {
+    let reader = Reader::new(reader).map_err(|err| err.to_string())?;
+    let root = Decoders::default().build(reader.writer_schema())?;
+    let columns = match &root.kind {
+        Kind::Record(decoders) => struct_fields(&root.dtype)
+            .into_iter()
+            .zip(decoders.iter().cloned())
+            .collect::<Vec<_>>(),
+        _ => vec![(Field::new("value", root.dtype.clone()), root.clone())],
+    };
+
+    let mut values = vec![Vec::new(); columns.len()];
+    for record in reader {
+        let record = record.map_err(|err| err.to_string())?;
+        match (&root.kind, record) {
+            (Kind::Record(_), AvroValue::Record(fields)) => {
+                for ((column, (_, value)), (_, decoder)) in
+                    values.iter_mut().zip(fields).zip(&columns)
+                {
+                    column.push(decoder.value(value)?);
+                }
+            }
+            (Kind::Record(_), _) => return Err(mismatch()),
+            (_, value) => values[0].push(root.value(value)?),
+        }
+    }
+
+    let series = columns
+        .iter()
+        .zip(&values)
+        .map(|((field, decoder), values)| series(field.name(), values, &decoder.dtype))
+        .collect::<Result<Vec<_>, _>>()?;
+    DataFrame::new(series).map_err(|err| err.to_string())
+}
+
+pub(crate) fn load_avro(path: &Path) -> Result<DataFrame, String> {
+    let file = File::open(path).map_err(|err| err.to_string())?;
+    read_avro(BufReader::new(file))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::io::Cursor;
+
+    use apache_avro::types::Value;
+    use apache_avro::{Decimal, Schema, Writer};
+    use polars::prelude::*;
+
+    use super::{read_avro, utc};
+
+    const SCHEMA: &str = r#"{
+        "type": "record",
+        "name": "event",
+        "fields": [
+            {"name": "id", "type": ["null", "long"]},
+            {"name": "point", "type": {"type": "record", "name": "point", "fields": [
+                {"name": "x", "type": "int"}
+            ]}},
+            {"name": "origin", "type": "point"},
+            {"name": "tags", "type": {"type": "array", "items": "string"}},
+            {"name": "day", "type": {"type": "int", "logicalType": "date"}},
+            {"name": "at", "type": {"type": "long", "logicalType": "timestamp-millis"}},
+            {"name": "at_us", "type": ["null", {"type": "long", "logicalType": "timestamp-micros"}]},
+            {"name": "local", "type": {"type": "long", "logicalType": "local-timestamp-millis"}},
+            {"name": "amount", "type": {"type": "bytes", "logicalType": "decimal", "precision": 6, "scale": 2}},
+            {"name": "attrs", "type": {"type": "map", "values": "long"}},
+            {"name": "either", "type": ["null", "string", "long"]}
+        ]
+    }"#;
+
+    fn event(id: Option<i64>, either: Value) -> Value {
+        let id = match id {
+            Some(id) => Value::Union(1, Box::new(Value::Long(id))),
+            None => Value::Union(0, Box::new(Value::Null)),
+        };
+        let point = |x| Value::Record(vec![("x".to_string(), Value::Int(x))]);
+        Value::Record(vec![
+            ("id".to_string(), id),
+            ("point".to_string(), point(3)),
+            ("origin".to_string(), point(0)),
+            (
+                "tags".to_string(),
+                Value::Array(vec![Value::String("a".to_string())]),
+            ),
+            ("day".to_string(), Value::Date(19_000)),
+            ("at".to_string(), Value::TimestampMillis(1_700_000_000_000)),
+            ("at_us".to_string(), Value::Union(0, Box::new(Value::Null))),
+            (
+                "local".to_string(),
+                Value::LocalTimestampMillis(1_700_000_000_000),
+            ),
+            (
+                "amount".to_string(),
+                Value::Decimal(Decimal::from(vec![0x04, 0xd2])),
+            ),
+            (
+                "attrs".to_string(),
+                Value::Map(HashMap::from([
+                    ("b".to_string(), Value::Long(2)),
+                    ("a".to_string(), Value::Long(1)),
+                ])),
+            ),
+            ("either".to_string(), either),
+        ])
+    }
+
+    fn fixture() -> DataFrame {
+        let schema = Schema::parse_str(SCHEMA).unwrap();
+        let mut writer = Writer::new(&schema, Vec::new());
+        writer
+            .append(event(Some(5), Value::Union(2, Box::new(Value::Long(7)))))
+            .unwrap();
+        writer
+            .append(event(
+                None,
+                Value::Union(1, Box::new(Value::String("x".into()))),
+            ))
+            .unwrap();
+        read_avro(Cursor::new(writer.into_inner().unwrap())).unwrap()
+    }
+
+    #[test]
+    fn maps_the_embedded_schema() {
+        let df = fixture();
+        let point = DataType::Struct(vec![Field::new("x", DataType::Int32)]);
+        let entry = DataType::Struct(vec![
+            Field::new("key", DataType::String),
+            Field::new("value", DataType::Int64),
+        ]);
+        let expected = [
+            ("id", DataType::Int64),
+            ("point", point.clone()),
+            ("origin", point),
+            ("tags", DataType::List(Box::new(DataType::String))),
+            ("day", DataType::Date),
+            ("at", utc(TimeUnit::Milliseconds)),
+            ("at_us", utc(TimeUnit::Microseconds)),
+            ("local", DataType::Datetime(TimeUnit::Milliseconds, None)),
+            ("amount", DataType::Float64),
+            ("attrs", DataType::List(Box::new(entry))),
+            ("either", DataType::String),
+        ];
+        let schema = df.schema();
+        for (name, dtype) in expected {
+            assert_eq!(schema.get(name), Some(&dtype), "{name}");
+        }
+    }
+
+    #[test]
+    fn reads_nullable_unions_and_logical_values() {
+        let df = fixture();
+        let value = |name: &str, row: usize| df.column(name).unwrap().get(row).unwrap();
+        assert_eq!(value("id", 0), AnyValue::Int64(5));
+        assert_eq!(value("id", 1), AnyValue::Null);
+        assert_eq!(value("day", 0), AnyValue::Date(19_000));
+        let zone = Some("UTC".to_string());
+        assert_eq!(
+            value("at", 0),
+            AnyValue::Datetime(1_700_000_000_000, TimeUnit::Milliseconds, &zone)
+        );
+        assert_eq!(
+            value("local", 0),
+            AnyValue::Datetime(1_700_000_000_000, TimeUnit::Milliseconds, &None)
+        );
+        assert_eq!(value("at_us", 0), AnyValue::Null);
+        assert_eq!(value("amount", 0), AnyValue::Float64(12.34));
+        assert_eq!(value("either", 0), AnyValue::String("7"));
+        assert_eq!(value("either", 1), AnyValue::String("x"));
+
+        let attrs = df.column("attrs").unwrap().list().unwrap().get_as_series(0);
+        let keys = attrs
+            .unwrap()
+            .struct_()
+            .unwrap()
+            .field_by_name("key")
+            .unwrap();
+        let keys = keys.str().unwrap().into_no_null_iter().collect::<Vec<_>>();
+        assert_eq!(keys, ["a", "b"]);
+    }
+}
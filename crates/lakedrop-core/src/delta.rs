@@ -0,0 +1,327 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::UNIX_EPOCH;
+
+use polars::prelude::*;
+use serde_json::Value;
+
+use crate::lakehouse::{percent_decode, scan_data_files, DataFile};
+use crate::TableVersion;
+
+pub(crate) struct DeltaSnapshot {
+    pub(crate) frame: LazyFrame,
+    pub(crate) version: i64,
+    pub(crate) history: Vec<TableVersion>,
+}
+
+#[derive(Default)]
+struct DeltaLog {
+    commits: BTreeMap<i64, PathBuf>,
+    checkpoints: BTreeMap<i64, Vec<PathBuf>>,
+}
+
+#[derive(Default)]
+struct Replay {
+    files: BTreeMap<String, Vec<(String, Option<String>)>>,
+    schema_string: Option<String>,
+}
+
+pub(crate) fn is_delta_table(path: &Path) -> bool {
+    path.is_dir() && path.join("_delta_log").is_dir()
+}
+
+fn parse_version(name: &str) -> Option<i64> {
+    let digits = name.split('.').next()?;
+    if digits.len() != 20 {
+        return None;
+    }
+    digits.parse().ok()
+}
+
+fn list_log(log_dir: &Path) -> Result<DeltaLog, String> {
+    let mut log = DeltaLog::default();
+    for entry in std::fs::read_dir(log_dir).map_err(|err| err.to_string())? {
+        let entry = entry.map_err(|err| err.to_string())?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        let Some(version) = parse_version(&name) else {
+            continue;
+        };
+        if name.ends_with(".json") && name.matches('.').count() == 1 {
+            log.commits.insert(version, entry.path());
+        } else if name.contains(".checkpoint.") && name.ends_with(".parquet") {
+            log.checkpoints
+                .entry(version)
+                .or_default()
+                .push(entry.path());
+        }
+    }
+    Ok(log)
+}
+
+fn partition_values(value: Option<&Value>) -> Vec<(String, Option<String>)> {
+    value
+        .and_then(Value::as_object)
+        .map(|values| {
+            values
+                .iter()
+                .map(|(key, value)| (key.clone(), value.as_str().map(str::to_string)))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn apply_action(replay: &mut Replay, action: &Value) -> Result<(), String> {
+    if let Some(add) = action.get("add") {
+        if add.get("deletionVector").is_some_and(|dv| !dv.is_null()) {
+            return Err("Delta tables with deletion vectors are not supported yet".to_string());
+        }
+        let path = add
+            .get("path")
+            .and_then(Value::as_str)
+            .ok_or("Delta add action is missing a path")?;
+        replay.files.insert(
+            path.to_string(),
+            partition_values(add.get("partitionValues")),
+        );
+    } else if let Some(remove) = action.get("remove") {
+        if let Some(path) = remove.get("path").and_then(Value::as_str) {
+            replay.files.remove(path);
+        }
+    } else if let Some(meta) = action.get("metaData") {
+        replay.schema_string = meta
+            .get("schemaString")
+            .and_then(Value::as_str)
+            .map(str::to_string);
+    }
+    Ok(())
+}
+
+fn apply_commit(replay: &mut Replay, path: &Path) -> Result<(), String> {
+    let file = File::open(path).map_err(|err| err.to_string())?;
+    for line in BufReader::new(file).lines() {
+        let line = line.map_err(|err| err.to_string())?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let action: Value = serde_json::from_str(&line).map_err(|err| err.to_string())?;
+        apply_action(replay, &action)?;
+    }
+    Ok(())
+}
+
+fn str_at(series: &Series, idx: usize) -> Option<String> {
+    series
+        .str()
+        .ok()
+        .and_then(|values| values.get(idx))
+        .map(str::to_string)
+}
+
+/// Loads the `add` and `metaData` rows of a (possibly multi-part) checkpoint.
+/// Checkpoints only carry live files, so their removes can be ignored.
+fn apply_checkpoint(replay: &mut Replay, parts: &[PathBuf]) -> Result<(), String> {
+    let df = LazyFrame::scan_parquet_files(Arc::new(parts.to_vec()), ScanArgsParquet::default())
+        .map_err(|err| err.to_string())?
+        .collect()
+        .map_err(|err| err.to_string())?;
+
+    if let Ok(adds) = df.column("add") {
+        let adds = adds.struct_().map_err(|err| err.to_string())?;
+        if let Ok(vectors) = adds.field_by_name("deletionVector") {
+            if vectors.null_count() != vectors.len() {
+                return Err(
+                    "Delta tables with deletion vectors are not supported yet".to_string(),
+                );
+            }
+        }
+        let paths = adds.field_by_name("path").map_err(|err| err.to_string())?;
+        let values = adds.field_by_name("partitionValues").ok();
+        for idx in 0..paths.len() {
+            let Some(path) = str_at(&paths, idx) else {
+                continue;
+            };
+            let mut partition = Vec::new();
+            if let Some(entries) = values
+                .as_ref()
+                .and_then(|values| values.list().ok())
+                .and_then(|values| values.get_as_series(idx))
+            {
+                let entries = entries.struct_().map_err(|err| err.to_string())?;
+                let keys = entries.field_by_name("key").map_err(|err| err.to_string())?;
+                let vals = entries
+                    .field_by_name("value")
+                    .map_err(|err| err.to_string())?;
+                for entry in 0..keys.len() {
+                    if let Some(key) = str_at(&keys, entry) {
+                        partition.push((key, str_at(&vals, entry)));
+                    }
+                }
+            }
+            replay.files.insert(path, partition);
+        }
+    }
+
+    if let Ok(meta) = df.column("metaData") {
+        let meta = meta.struct_().map_err(|err| err.to_string())?;
+        let schemas = meta
+            .field_by_name("schemaString")
+            .map_err(|err| err.to_string())?;
+        if let Some(idx) = (0..schemas.len()).find(|idx| str_at(&schemas, *idx).is_some()) {
+            replay.schema_string = str_at(&schemas, idx);
+        }
+    }
+
+    Ok(())
+}
+
+fn delta_type(value: &Value) -> DataType {
+    match value.as_str() {
+        Some("string") => DataType::String,
+        Some("long") => DataType::Int64,
+        Some("integer") => DataType::Int32,
+        Some("short") => DataType::Int16,
+        Some("byte") => DataType::Int8,
+        Some("float") => DataType::Float32,
+        Some("double") => DataType::Float64,
+        Some("boolean") => DataType::Boolean,
+        Some("binary") => DataType::Binary,
+        Some("date") => DataType::Date,
+        Some("timestamp") => DataType::Datetime(TimeUnit::Microseconds, Some("UTC".to_string())),
+        Some("timestamp_ntz") => DataType::Datetime(TimeUnit::Microseconds, None),
+        Some(other) if other.starts_with("decimal") => DataType::Float64,
+        _ => DataType::String,
+    }
+}
+
+fn table_schema(schema_string: Option<&str>) -> Result<Schema, String> {
+    let Some(schema_string) = schema_string else {
+        return Ok(Schema::new());
+    };
+    let schema: Value = serde_json::from_str(schema_string).map_err(|err| err.to_string())?;
+    let fields = schema
+        .get("fields")
+        .and_then(Value::as_array)
+        .cloned()
+        .unwrap_or_default();
+    Ok(fields
+        .iter()
+        .filter_map(|field| {
+            let name = field.get("name")?.as_str()?;
+            let dtype = delta_type(field.get("type")?);
+            Some(Field::new(name, dtype))
+        })
+        .collect())
+}
+
+fn commit_info(path: &Path) -> (Option<i64>, Option<String>) {
+    let info = File::open(path).ok().and_then(|file| {
+        BufReader::new(file)
+            .lines()
+            .map_while(Result::ok)
+            .filter_map(|line| serde_json::from_str::<Value>(&line).ok())
+            .find_map(|action| action.get("commitInfo").cloned())
+    });
+    let timestamp = info
+        .as_ref()
+        .and_then(|info| info.get("timestamp"))
+        .and_then(Value::as_i64)
+        .or_else(|| {
+            std::fs::metadata(path)
+                .and_then(|meta| meta.modified())
+                .ok()
+                .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                .map(|elapsed| elapsed.as_millis() as i64)
+        });
+    let operation = info
+        .as_ref()
+        .and_then(|info| info.get("operation"))
+        .and_then(Value::as_str)
+        .map(str::to_string);
+    (timestamp, operation)
+}
+
+fn history(log: &DeltaLog) -> Vec<TableVersion> {
+    let versions = log
+        .commits
+        .keys()
+        .chain(log.checkpoints.keys())
+        .copied()
+        .collect::<BTreeSet<_>>();
+    versions
+        .into_iter()
+        .rev()
+        .map(|version| {
+            let (timestamp_ms, operation) = log
+                .commits
+                .get(&version)
+                .map(|path| commit_info(path))
+                .unwrap_or((None, None));
+            TableVersion {
+                id: version.to_string(),
+                timestamp_ms,
+                operation,
+            }
+        })
+        .collect()
+}
+
+fn resolve_data_path(root: &Path, path: &str) -> PathBuf {
+    let decoded = percent_decode(path);
+    match decoded.strip_prefix("file://") {
+        Some(absolute) => PathBuf::from(absolute),
+        None if Path::new(&decoded).is_absolute() => PathBuf::from(decoded),
+        None => root.join(decoded),
+    }
+}
+
+/// Replays the `_delta_log` of the table at `root` up to `version` (the
+/// latest version when `None`), starting from the newest usable checkpoint.
+pub(crate) fn load_delta(root: &Path, version: Option<i64>) -> Result<DeltaSnapshot, String> {
+    let log = list_log(&root.join("_delta_log"))?;
+    let latest = log
+        .commits
+        .keys()
+        .chain(log.checkpoints.keys())
+        .max()
+        .copied()
+        .ok_or("Delta log is empty")?;
+    let target = version.unwrap_or(latest);
+    if target < 0 || target > latest {
+        return Err(format!("Delta version {target} does not exist"));
+    }
+
+    let mut replay = Replay::default();
+    let start = match log.checkpoints.range(..=target).next_back() {
+        Some((checkpoint, parts)) => {
+            apply_checkpoint(&mut replay, parts)?;
+            checkpoint + 1
+        }
+        None => 0,
+    };
+    for commit in start..=target {
+        let path = log.commits.get(&commit).ok_or_else(|| {
+            format!("Delta log for version {commit} is missing; it may have been cleaned up")
+        })?;
+        apply_commit(&mut replay, path)?;
+    }
+
+    let schema = table_schema(replay.schema_string.as_deref())?;
+    let files = replay
+        .files
+        .into_iter()
+        .map(|(path, partition_values)| DataFile {
+            path: resolve_data_path(root, &path),
+            partition_values,
+            columns: None,
+        })
+        .collect::<Vec<_>>();
+
+    Ok(DeltaSnapshot {
+        frame: scan_data_files(&files, &schema)?,
+        version: target,
+        history: history(&log),
+    })
+}
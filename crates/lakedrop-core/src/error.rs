@@ -0,0 +1,332 @@
+use std::fmt;
+use std::path::Path;
+
+use polars::prelude::PolarsError;
+use serde::Serialize;
+
+use crate::sql;
+
+pub(crate) const NO_FILE: &str = "No file loaded. Drag a file to begin.";
+
+/// How sqlparser starts its messages, also when polars-sql passes them on.
+const PARSER_PREFIX: &str = "sql parser error: ";
+
+/// What went wrong, stable enough for a UI to branch on and to pick a
+/// localized message.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    FileNotFound,
+    UnsupportedFormat,
+    UnsupportedCompression,
+    ReadFailed,
+    NoSource,
+    SqlSyntax,
+    UnknownTable,
+    UnknownColumn,
+    QueryFailed,
+    UnsupportedExport,
+    ExportFailed,
+    Other,
+}
+
+/// A position in the SQL text: 1-based `line`/`column` in characters for
+/// display, and a range `start..end` for highlighting. `start` and `end`
+/// count UTF-16 code units, the offsets JavaScript strings and the editor
+/// use; they differ from character counts after characters outside the
+/// Basic Multilingual Plane, such as emoji. `end == start` when only the
+/// position is known.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub struct SqlSpan {
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl SqlSpan {
+    /// The span of `len` characters starting at 1-based `line`/`column`,
+    /// both counted in characters the way sqlparser reports them.
+    pub fn at(sql: &str, line: usize, column: usize, len: usize) -> Self {
+        let first = sql
+            .split_inclusive('\n')
+            .take(line.saturating_sub(1))
+            .map(|text| text.chars().count())
+            .sum::<usize>()
+            + column.saturating_sub(1);
+        let units = |chars: usize| sql.chars().take(chars).map(char::len_utf16).sum::<usize>();
+        SqlSpan {
+            start: units(first),
+            end: units(first + len),
+            line,
+            column,
+        }
+    }
+}
+
+/// Error returned by the loading, query and export API. `message` is plain
+/// English; UIs can localize by `code` and fill in `path` or `identifier`.
+#[derive(Clone, Debug, Serialize)]
+pub struct Error {
+    pub code: ErrorCode,
+    pub message: String,
+    pub span: Option<SqlSpan>,
+    pub path: Option<String>,
+    pub hint: Option<String>,
+    /// The unknown table or column name.
+    pub identifier: Option<String>,
+    /// Known names close to `identifier`, best first.
+    pub suggestions: Vec<String>,
+}
+
+impl Error {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Error {
+            code,
+            message: message.into(),
+            span: None,
+            path: None,
+            hint: None,
+            identifier: None,
+            suggestions: Vec::new(),
+        }
+    }
+
+    pub fn with_path(mut self, path: impl AsRef<Path>) -> Self {
+        self.path = Some(path.as_ref().display().to_string());
+        self
+    }
+
+    pub fn with_hint(mut self, hint: impl Into<String>) -> Self {
+        self.hint = Some(hint.into());
+        self
+    }
+
+    pub fn with_span(mut self, span: SqlSpan) -> Self {
+        self.span = Some(span);
+        self
+    }
+
+    /// Also sets the hint to the best suggestion, if there is one.
+    pub fn with_suggestions(mut self, suggestions: Vec<String>) -> Self {
+        if let Some(best) = suggestions.first() {
+            self.hint = Some(format!("Did you mean `{best}`?"));
+        }
+        self.suggestions = suggestions;
+        self
+    }
+
+    pub(crate) fn file_not_found(path: &Path) -> Self {
+        Error::new(
+            ErrorCode::FileNotFound,
+            format!("File not found: {}", path.display()),
+        )
+        .with_path(path)
+    }
+
+    pub(crate) fn read_failed(path: &Path, message: impl Into<String>) -> Self {
+        Error::new(ErrorCode::ReadFailed, message).with_path(path)
+    }
+
+    pub(crate) fn no_source() -> Self {
+        Error::new(ErrorCode::NoSource, NO_FILE)
+    }
+
+    /// A parser error, positioned where sqlparser says it stopped. The
+    /// "sql parser error: " prefix is dropped; the code already says so.
+    pub(crate) fn syntax(sql: &str, message: String) -> Self {
+        let message = match message.strip_prefix(PARSER_PREFIX) {
+            Some(rest) => rest.to_string(),
+            None => message,
+        };
+        let span = parser_span(sql, &message);
+        let error = Error::new(ErrorCode::SqlSyntax, message);
+        match span {
+            Some(span) => error.with_span(span),
+            None => error,
+        }
+    }
+
+    /// Classifies a failure of planning or running `sql`. Unknown tables and
+    /// columns are pointed at where they first appear in `sql`; see
+    /// [`crate::Session::explain`] for suggestions.
+    pub fn from_query(err: PolarsError, sql: &str) -> Self {
+        let message = err.to_string();
+        match err {
+            PolarsError::SQLInterface(_) if message.starts_with(PARSER_PREFIX) => {
+                Error::syntax(sql, message)
+            }
+            PolarsError::SQLSyntax(_) => Error::new(ErrorCode::SqlSyntax, message),
+            // Not "relation 'x' already exists", which CREATE TABLE raises.
+            PolarsError::SQLInterface(_)
+                if message.starts_with("relation '") && message.contains("' was not found") =>
+            {
+                let error = match quoted(&message, "relation '", '\'') {
+                    Some(name) => Error::unknown(ErrorCode::UnknownTable, "table", name, sql),
+                    None => Error::new(ErrorCode::UnknownTable, message),
+                };
+                error.with_hint("Query `source` or one of the registered tables.")
+            }
+            PolarsError::SQLInterface(_) if message.starts_with("no column named '") => {
+                match quoted(&message, "no column named '", '\'') {
+                    Some(name) => Error::unknown(ErrorCode::UnknownColumn, "column", name, sql),
+                    None => Error::new(ErrorCode::UnknownColumn, message),
+                }
+            }
+            PolarsError::ColumnNotFound(name) => {
+                // Either a bare name or "unable to find column \"x\"; valid
+                // columns: [...]", often followed by the plan.
+                let name = quoted(&name, "unable to find column \"", '"')
+                    .unwrap_or_else(|| name.lines().next().unwrap_or_default().trim_matches('"'));
+                Error::unknown(ErrorCode::UnknownColumn, "column", name, sql)
+            }
+            _ => Error::new(ErrorCode::QueryFailed, message),
+        }
+    }
+
+    fn unknown(code: ErrorCode, what: &str, name: &str, sql: &str) -> Self {
+        let mut error = Error::new(code, format!("Unknown {what} \"{name}\""));
+        error.identifier = Some(name.to_string());
+        match sql::locate(sql, name) {
+            Some(span) => error.with_span(span),
+            None => error,
+        }
+    }
+}
+
+/// The text after `prefix` up to the next `close`.
+fn quoted<'a>(message: &'a str, prefix: &str, close: char) -> Option<&'a str> {
+    let start = message.find(prefix)? + prefix.len();
+    let rest = &message[start..];
+    rest.find(close).map(|end| &rest[..end])
+}
+
+/// Reads the position sqlparser appends to its messages, e.g.
+/// `Expected end of statement, found: frm at Line: 1, Column 10`.
+fn parser_span(sql: &str, message: &str) -> Option<SqlSpan> {
+    let (head, location) = message.rsplit_once(" at Line: ")?;
+    let (line, column) = location.split_once(", Column")?;
+    let line = line.trim().parse().ok()?;
+    let column = column
+        .trim_start_matches(':')
+        .trim()
+        .split(|ch: char| !ch.is_ascii_digit())
+        .next()?
+        .parse()
+        .ok()?;
+    let found = head
+        .rsplit_once("found: ")
+        .map(|(_, found)| found)
+        .filter(|found| *found != "EOF");
+    Some(SqlSpan::at(
+        sql,
+        line,
+        column,
+        found.map_or(0, |found| found.chars().count()),
+    ))
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<String> for Error {
+    fn from(message: String) -> Self {
+        Error::new(ErrorCode::Other, message)
+    }
+}
+
+impl From<&str> for Error {
+    fn from(message: &str) -> Self {
+        Error::new(ErrorCode::Other, message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parser_span_covers_the_found_token() {
+        let span = parser_span(
+            "SELECT 1 frm",
+            "Expected end of statement, found: frm at Line: 1, Column 10",
+        );
+        assert_eq!(
+            span,
+            Some(SqlSpan {
+                start: 9,
+                end: 12,
+                line: 1,
+                column: 10,
+            })
+        );
+    }
+
+    #[test]
+    fn parser_span_counts_earlier_lines() {
+        let sql = "SELECT 1\nFROM t\nWHRE x";
+        let span = parser_span(
+            sql,
+            "Expected end of statement, found: x at Line: 3, Column 6",
+        );
+        assert_eq!(span.map(|span| (span.start, span.end)), Some((21, 22)));
+        assert_eq!(&sql[21..22], "x");
+    }
+
+    #[test]
+    fn parser_span_is_empty_at_the_end_of_input() {
+        let span = parser_span(
+            "SELECT",
+            "Expected an expression, found: EOF at Line: 1, Column 7",
+        );
+        assert_eq!(span.map(|span| (span.start, span.end)), Some((6, 6)));
+        assert_eq!(parser_span("SELECT", "Expected an expression"), None);
+    }
+
+    #[test]
+    fn parser_span_counts_utf16_units() {
+        let sql = "SELECT '𝄞' frm";
+        let span = parser_span(
+            sql,
+            "Expected end of statement, found: frm at Line: 1, Column 12",
+        )
+        .unwrap();
+        assert_eq!((span.start, span.end), (12, 15));
+        assert_eq!(span.column, 12);
+    }
+
+    #[test]
+    fn syntax_drops_the_parser_prefix() {
+        let err = Error::syntax(
+            "SELECT 1 frm",
+            "sql parser error: Expected end of statement, found: frm at Line: 1, Column 10"
+                .to_string(),
+        );
+        assert_eq!(err.code, ErrorCode::SqlSyntax);
+        assert!(err.message.starts_with("Expected end of statement"));
+        assert!(err.span.is_some());
+    }
+
+    #[test]
+    fn from_query_classifies_relations() {
+        let sql = "SELECT * FROM ordrs";
+        let err = Error::from_query(
+            PolarsError::SQLInterface("relation 'ordrs' was not found".into()),
+            sql,
+        );
+        assert_eq!(err.code, ErrorCode::UnknownTable);
+        assert_eq!(err.identifier.as_deref(), Some("ordrs"));
+        assert_eq!(err.span.map(|span| span.start), Some(14));
+
+        let err = Error::from_query(
+            PolarsError::SQLInterface("relation 'orders' already exists".into()),
+            "CREATE TABLE orders AS SELECT 1",
+        );
+        assert_eq!(err.code, ErrorCode::QueryFailed);
+    }
+}
@@ -0,0 +1,334 @@
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+use calamine::{open_workbook_auto, Data, Range, Reader, Sheets};
+use polars::prelude::*;
+use serde::Serialize;
+
+/// Days between the Excel epoch (1899-12-30) and 1970-01-01.
+const EXCEL_UNIX_EPOCH_DAYS: f64 = 25569.0;
+const MS_PER_DAY: f64 = 86_400_000.0;
+
+/// A rectangular block of cells on one sheet, zero-based and inclusive.
+#[derive(Clone)]
+struct CellRange {
+    sheet: String,
+    start: (u32, u32),
+    end: (u32, u32),
+}
+
+fn excel_cell_to_string(cell: &Data) -> Option<String> {
+    match cell {
+        Data::Empty => None,
+        Data::String(value) => Some(value.to_string()),
+        Data::Float(value) => Some(value.to_string()),
+        Data::Int(value) => Some(value.to_string()),
+        Data::Bool(value) => Some(value.to_string()),
+        Data::DateTime(value) => Some(value.to_string()),
+        Data::DateTimeIso(value) => Some(value.to_string()),
+        Data::DurationIso(value) => Some(value.to_string()),
+        Data::Error(value) => Some(format!("{value:?}")),
+    }
+}
+
+/// Parses `A1` or `$A$1` into a zero-based `(row, column)`.
+fn parse_cell(cell: &str) -> Option<(u32, u32)> {
+    let cell = cell.replace('$', "");
+    let split = cell.find(|ch: char| ch.is_ascii_digit())?;
+    let (letters, digits) = cell.split_at(split);
+    if letters.is_empty() || !letters.chars().all(|ch| ch.is_ascii_alphabetic()) {
+        return None;
+    }
+    let column = letters.chars().fold(0u32, |acc, ch| {
+        acc * 26 + (ch.to_ascii_uppercase() as u32 - 'A' as u32 + 1)
+    });
+    let row = digits.parse::<u32>().ok()?;
+    Some((row.checked_sub(1)?, column - 1))
+}
+
+/// Splits `Sheet1!$A$1`, `'My Sheet'!A1` (Excel) or `$Sheet1.$A$1` (ODS)
+/// into the sheet name, if any, and the cell.
+fn split_reference(reference: &str) -> (Option<String>, &str) {
+    match reference.rsplit_once(['!', '.']) {
+        Some((sheet, cell)) => {
+            let sheet = sheet.trim_start_matches('$');
+            let sheet = sheet
+                .strip_prefix('\'')
+                .and_then(|rest| rest.strip_suffix('\''))
+                .map(|name| name.replace("''", "'"))
+                .unwrap_or_else(|| sheet.to_string());
+            ((!sheet.is_empty()).then_some(sheet), cell)
+        }
+        None => (None, reference),
+    }
+}
+
+/// Resolves a defined name's formula to a single rectangular range. Formulas,
+/// constants and multi-area names are not selectable and yield `None`.
+fn parse_reference(formula: &str) -> Option<CellRange> {
+    let formula = formula.trim().trim_start_matches('=');
+    if formula.contains(',') || formula.contains(';') {
+        return None;
+    }
+    let (first, last) = formula.split_once(':').unwrap_or((formula, formula));
+    let (sheet, start) = split_reference(first);
+    let (end_sheet, end) = split_reference(last);
+    let sheet = sheet.or(end_sheet)?;
+    let start = parse_cell(start)?;
+    let end = parse_cell(end)?;
+    Some(CellRange {
+        sheet,
+        start: (start.0.min(end.0), start.1.min(end.1)),
+        end: (start.0.max(end.0), start.1.max(end.1)),
+    })
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RangeKind {
+    /// An Excel Table (ListObject), read with its own header row.
+    Table,
+    /// A defined name pointing at a cell range; its first row is the header.
+    Name,
+}
+
+/// A named part of a workbook that can be loaded instead of a whole sheet.
+#[derive(Clone, Serialize)]
+pub struct WorkbookRange {
+    pub name: String,
+    pub kind: RangeKind,
+    pub sheet: String,
+    #[serde(skip)]
+    cells: Option<CellRange>,
+}
+
+/// Everything the UI needs after opening a workbook entry.
+pub(crate) struct WorkbookSelection {
+    pub(crate) frame: DataFrame,
+    pub(crate) sheets: Vec<String>,
+    pub(crate) ranges: Vec<WorkbookRange>,
+    pub(crate) active: String,
+}
+
+/// Excel Tables (`.xlsx` only) followed by defined names that point at a
+/// cell range, in workbook order. Built-in names such as `_xlnm.Print_Area`
+/// are skipped.
+fn workbook_ranges(
+    workbook: &mut Sheets<BufReader<File>>,
+    sheets: &[String],
+) -> Result<Vec<WorkbookRange>, String> {
+    let mut ranges = Vec::new();
+    if let Sheets::Xlsx(xlsx) = &mut *workbook {
+        xlsx.load_tables().map_err(|err| err.to_string())?;
+        for sheet in sheets {
+            ranges.extend(
+                xlsx.table_names_in_sheet(sheet)
+                    .into_iter()
+                    .map(|name| WorkbookRange {
+                        name: name.clone(),
+                        kind: RangeKind::Table,
+                        sheet: sheet.clone(),
+                        cells: None,
+                    }),
+            );
+        }
+    }
+
+    let names = workbook
+        .defined_names()
+        .iter()
+        .filter(|(name, _)| !name.starts_with("_xlnm.") && !sheets.contains(name))
+        .filter_map(|(name, formula)| {
+            parse_reference(formula)
+                .filter(|cells| sheets.contains(&cells.sheet))
+                .map(|cells| WorkbookRange {
+                    name: name.clone(),
+                    kind: RangeKind::Name,
+                    sheet: cells.sheet.clone(),
+                    cells: Some(cells),
+                })
+        })
+        .collect::<Vec<_>>();
+    ranges.extend(names);
+    Ok(ranges)
+}
+
+fn excel_serial_to_ms(value: f64) -> i64 {
+    ((value - EXCEL_UNIX_EPOCH_DAYS) * MS_PER_DAY).round() as i64
+}
+
+/// Builds a typed column: all-integer cells become Int64, numbers Float64,
+/// booleans Boolean and date cells Datetime. Anything mixed stays a string.
+fn cells_to_series(name: &str, cells: &[&Data]) -> Result<Series, String> {
+    let filled = cells
+        .iter()
+        .copied()
+        .filter(|cell| !matches!(cell, Data::Empty))
+        .collect::<Vec<_>>();
+    let all =
+        |check: fn(&Data) -> bool| !filled.is_empty() && filled.iter().all(|cell| check(cell));
+
+    if all(|cell| matches!(cell, Data::Int(_))) {
+        let values = cells
+            .iter()
+            .map(|cell| match cell {
+                Data::Int(value) => Some(*value),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        return Ok(Series::new(name, values));
+    }
+    if all(|cell| matches!(cell, Data::Int(_) | Data::Float(_))) {
+        let values = cells
+            .iter()
+            .map(|cell| match cell {
+                Data::Int(value) => Some(*value as f64),
+                Data::Float(value) => Some(*value),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        return Ok(Series::new(name, values));
+    }
+    if all(|cell| matches!(cell, Data::Bool(_))) {
+        let values = cells
+            .iter()
+            .map(|cell| match cell {
+                Data::Bool(value) => Some(*value),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        return Ok(Series::new(name, values));
+    }
+    if all(|cell| matches!(cell, Data::DateTime(value) if !value.is_duration())) {
+        let values = cells
+            .iter()
+            .map(|cell| match cell {
+                Data::DateTime(value) => Some(excel_serial_to_ms(value.as_f64())),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        return Series::new(name, values)
+            .cast(&DataType::Datetime(TimeUnit::Milliseconds, None))
+            .map_err(|err| err.to_string());
+    }
+
+    let values = cells
+        .iter()
+        .map(|cell| excel_cell_to_string(cell))
+        .collect::<Vec<_>>();
+    Ok(Series::new(name, values))
+}
+
+fn rows_to_frame<'a>(
+    mut headers: Vec<String>,
+    rows: impl Iterator<Item = &'a [Data]>,
+) -> Result<DataFrame, String> {
+    let mut columns: Vec<Vec<&Data>> = vec![Vec::new(); headers.len()];
+    for row in rows {
+        if row.len() > headers.len() {
+            let start = headers.len();
+            headers.extend((start..row.len()).map(|idx| format!("col_{}", idx + 1)));
+            columns.extend((start..row.len()).map(|_| Vec::new()));
+        }
+        for (idx, col) in columns.iter_mut().enumerate() {
+            col.push(row.get(idx).unwrap_or(&Data::Empty));
+        }
+    }
+
+    let series = headers
+        .iter()
+        .zip(columns)
+        .map(|(name, cells)| cells_to_series(name, &cells))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    DataFrame::new(series).map_err(|err| err.to_string())
+}
+
+/// Turns a cell range into a frame, using its first row as the header.
+fn range_to_frame(range: &Range<Data>) -> Result<DataFrame, String> {
+    let mut rows = range.rows();
+    let headers = rows
+        .next()
+        .map(|row| {
+            row.iter()
+                .enumerate()
+                .map(|(idx, cell)| {
+                    excel_cell_to_string(cell)
+                        .filter(|value| !value.trim().is_empty())
+                        .unwrap_or_else(|| format!("col_{}", idx + 1))
+                })
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    rows_to_frame(headers, rows)
+}
+
+fn load_range(
+    workbook: &mut Sheets<BufReader<File>>,
+    range: &WorkbookRange,
+) -> Result<DataFrame, String> {
+    match (&range.cells, workbook) {
+        (Some(cells), workbook) => {
+            let sheet = workbook
+                .worksheet_range(&cells.sheet)
+                .map_err(|err| err.to_string())?;
+            range_to_frame(&sheet.range(cells.start, cells.end))
+        }
+        // Table bounds come from the table definition: the header row is
+        // excluded from the data and its column names are used as-is.
+        (None, Sheets::Xlsx(xlsx)) => {
+            let table = xlsx
+                .table_by_name(&range.name)
+                .map_err(|err| err.to_string())?;
+            rows_to_frame(table.columns().to_vec(), table.data().rows())
+        }
+        (None, _) => Err(format!("Table not found: {}", range.name)),
+    }
+}
+
+/// Loads a worksheet, an Excel Table or a defined name (a named cell range)
+/// from any workbook calamine can open: `.xlsx`, `.xlsm`, `.xls`, `.xlsb` and
+/// `.ods`. Defaults to the first sheet.
+pub(crate) fn load_excel_sheet(
+    path: &Path,
+    sheet_name: Option<String>,
+) -> Result<WorkbookSelection, String> {
+    let mut workbook = open_workbook_auto(path).map_err(|err| err.to_string())?;
+    let sheets = workbook.sheet_names().to_vec();
+    let ranges = workbook_ranges(&mut workbook, &sheets)?;
+    let active = sheet_name
+        .or_else(|| sheets.first().cloned())
+        .ok_or("No sheets found in workbook")?;
+
+    let frame = if sheets.contains(&active) {
+        let range = workbook
+            .worksheet_range(&active)
+            .map_err(|err| err.to_string())?;
+        range_to_frame(&range)?
+    } else {
+        let range = ranges
+            .iter()
+            .find(|range| range.name == active)
+            .ok_or_else(|| format!("Sheet or range not found: {active}"))?;
+        load_range(&mut workbook, range)?
+    };
+
+    Ok(WorkbookSelection {
+        frame,
+        sheets,
+        ranges,
+        active,
+    })
+}
+
+/// Loads every Excel Table and named range of a workbook, keyed by name.
+pub(crate) fn load_excel_ranges(path: &Path) -> Result<Vec<(String, DataFrame)>, String> {
+    let mut workbook = open_workbook_auto(path).map_err(|err| err.to_string())?;
+    let sheets = workbook.sheet_names().to_vec();
+    let ranges = workbook_ranges(&mut workbook, &sheets)?;
+    ranges
+        .iter()
+        .map(|range| Ok((range.name.clone(), load_range(&mut workbook, range)?)))
+        .collect()
+}
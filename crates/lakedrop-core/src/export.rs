@@ -0,0 +1,366 @@
+use std::fs::File;
+use std::path::Path;
+
+use polars::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{any_value_to_json, sqlite, Error, ErrorCode};
+
+/// How [`crate::Session::export`] writes a query result.
+#[derive(Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ExportOptions {
+    /// `csv`, `parquet`, `xlsx` or `sqlite`.
+    pub format: String,
+    /// Write one file per distinct value combination into Hive-style
+    /// `column=value` directories below the target path.
+    pub partition_by: Vec<String>,
+    /// Also write `_manifest.json` next to a partitioned export.
+    pub write_manifest: bool,
+    /// SQLite only: the table to create, `source` by default.
+    pub table_name: Option<String>,
+    /// `replace` (default) or `append`: what happens to the SQLite table, or
+    /// to the files an earlier export into the same folder listed in its
+    /// `_manifest.json`.
+    pub if_exists: Option<String>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum WriteMode {
+    Replace,
+    Append,
+}
+
+impl WriteMode {
+    pub(crate) fn parse(value: Option<&str>) -> Result<Self, Error> {
+        match value.unwrap_or("replace") {
+            "replace" => Ok(Self::Replace),
+            "append" => Ok(Self::Append),
+            other => Err(Error::new(
+                ErrorCode::UnsupportedExport,
+                format!("Unsupported write mode: {other}"),
+            )
+            .with_hint("Use replace or append.")),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct PartitionValue {
+    column: String,
+    value: serde_json::Value,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ManifestEntry {
+    path: String,
+    row_count: u64,
+    partition: Vec<PartitionValue>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ExportManifest {
+    format: String,
+    partition_by: Vec<String>,
+    total_rows: u64,
+    files: Vec<ManifestEntry>,
+}
+
+pub fn export_extension(format: &str) -> Result<&'static str, Error> {
+    match format {
+        "csv" => Ok("csv"),
+        "xlsx" => Ok("xlsx"),
+        "parquet" => Ok("parquet"),
+        _ => Err(Error::new(
+            ErrorCode::UnsupportedExport,
+            format!("Unsupported export format: {format}"),
+        )
+        .with_hint("Export as csv, parquet, xlsx or sqlite.")),
+    }
+}
+
+fn export_failed(path: &Path, message: String) -> Error {
+    Error::new(ErrorCode::ExportFailed, message)
+        .with_path(path)
+        .with_hint("Check that the target folder exists and is writable.")
+}
+
+pub fn write_export(df: &DataFrame, path: &Path, format: &str) -> Result<(), Error> {
+    export_extension(format)?;
+    write_file(df, path, format).map_err(|message| export_failed(path, message))
+}
+
+fn write_file(df: &DataFrame, path: &Path, format: &str) -> Result<(), String> {
+    match format {
+        "csv" => {
+            let mut file = File::create(path).map_err(|err| err.to_string())?;
+            let mut df = df.clone();
+            CsvWriter::new(&mut file)
+                .finish(&mut df)
+                .map_err(|err| err.to_string())?;
+        }
+        "parquet" => {
+            let mut file = File::create(path).map_err(|err| err.to_string())?;
+            let mut df = df.clone();
+            ParquetWriter::new(&mut file)
+                .finish(&mut df)
+                .map_err(|err| err.to_string())?;
+        }
+        "xlsx" => {
+            let mut book = umya_spreadsheet::new_file();
+            let sheet = book
+                .get_sheet_by_name_mut("Sheet1")
+                .ok_or("Missing sheet")?;
+            for (col_idx, name) in df.get_column_names().iter().enumerate() {
+                sheet
+                    .get_cell_mut(((col_idx + 1) as u32, 1u32))
+                    .set_value(name.to_string());
+            }
+            for row_idx in 0..df.height() {
+                for (col_idx, series) in df.get_columns().iter().enumerate() {
+                    let value = series.get(row_idx).map_err(|err| err.to_string())?;
+                    sheet
+                        .get_cell_mut(((col_idx + 1) as u32, (row_idx + 2) as u32))
+                        .set_value(value.to_string());
+                }
+            }
+            umya_spreadsheet::writer::xlsx::write(&book, path).map_err(|err| err.to_string())?;
+        }
+        _ => return Err("Unsupported export format".to_string()),
+    }
+    Ok(())
+}
+
+/// Renders a partition value the way Hive names its directories.
+fn partition_dir_value(value: &AnyValue) -> String {
+    let raw = match value {
+        AnyValue::Null => return "__HIVE_DEFAULT_PARTITION__".to_string(),
+        AnyValue::String(value) => value.to_string(),
+        AnyValue::StringOwned(value) => value.to_string(),
+        other => other.to_string(),
+    };
+    let mut escaped = String::with_capacity(raw.len());
+    for ch in raw.chars() {
+        match ch {
+            '/' | '\\' | '=' | '%' | ':' | '*' | '?' | '"' | '<' | '>' | '|' | '#' => {
+                escaped.push_str(&format!("%{:02X}", ch as u32));
+            }
+            ch if ch.is_control() => escaped.push_str(&format!("%{:02X}", ch as u32)),
+            ch => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+/// The files an earlier export recorded, when appending to it.
+fn read_manifest(out_dir: &Path) -> Option<ExportManifest> {
+    let file = File::open(out_dir.join("_manifest.json")).ok()?;
+    serde_json::from_reader(file).ok()
+}
+
+/// Removes the files an earlier export listed in its manifest, the
+/// partition directories that leaves empty and the manifest itself, so
+/// readers of the folder do not count old rows alongside the new ones.
+/// Nothing else in the folder is touched; partition directories that no
+/// manifest accounts for are refused rather than guessed at.
+fn clear_previous(out_dir: &Path, first_column: &str) -> Result<(), String> {
+    let Some(previous) = read_manifest(out_dir) else {
+        let prefix = format!("{first_column}=");
+        let has_partitions = std::fs::read_dir(out_dir)
+            .map_err(|err| err.to_string())?
+            .filter_map(Result::ok)
+            .any(|entry| entry.file_name().to_string_lossy().starts_with(&prefix));
+        if has_partitions {
+            return Err(format!(
+                "{} has {prefix}... folders that no _manifest.json lists; \
+                 export into an empty folder or remove them first",
+                out_dir.display()
+            ));
+        }
+        return Ok(());
+    };
+
+    for entry in &previous.files {
+        let parts = entry.path.split('/').collect::<Vec<_>>();
+        if parts
+            .iter()
+            .any(|part| part.is_empty() || *part == "." || *part == "..")
+        {
+            return Err(format!(
+                "_manifest.json lists a file outside the export: {}",
+                entry.path
+            ));
+        }
+        let path = parts
+            .iter()
+            .fold(out_dir.to_path_buf(), |path, part| path.join(part));
+        match std::fs::remove_file(&path) {
+            Ok(()) => {}
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+            Err(err) => return Err(err.to_string()),
+        }
+        // remove_dir fails on the first directory that still has files
+        let mut dir = path.parent();
+        while let Some(parent) = dir.filter(|dir| *dir != out_dir) {
+            if std::fs::remove_dir(parent).is_err() {
+                break;
+            }
+            dir = parent.parent();
+        }
+    }
+    std::fs::remove_file(out_dir.join("_manifest.json")).map_err(|err| err.to_string())
+}
+
+fn export_partitioned(
+    df: DataFrame,
+    out_dir: &Path,
+    format: &str,
+    partition_by: &[String],
+    write_manifest: bool,
+    mode: WriteMode,
+) -> Result<(), String> {
+    let ext = export_extension(format).map_err(|err| err.message)?;
+    for name in partition_by {
+        df.column(name).map_err(|err| err.to_string())?;
+    }
+    std::fs::create_dir_all(out_dir).map_err(|err| err.to_string())?;
+    let previous = match mode {
+        WriteMode::Replace => {
+            clear_previous(out_dir, &partition_by[0])?;
+            None
+        }
+        WriteMode::Append => read_manifest(out_dir),
+    };
+
+    let partitions = df
+        .partition_by_stable(partition_by.to_vec(), true)
+        .map_err(|err| err.to_string())?;
+
+    let mut files = Vec::with_capacity(partitions.len());
+    for partition in partitions {
+        let mut dir = out_dir.to_path_buf();
+        let mut values = Vec::with_capacity(partition_by.len());
+        for name in partition_by {
+            let value = partition
+                .column(name)
+                .and_then(|series| series.get(0))
+                .map_err(|err| err.to_string())?;
+            let dir_value = partition_dir_value(&value);
+            dir.push(format!("{name}={dir_value}"));
+            values.push(PartitionValue {
+                column: name.clone(),
+                value: any_value_to_json(value),
+            });
+        }
+        std::fs::create_dir_all(&dir).map_err(|err| err.to_string())?;
+
+        let data = partition.drop_many(partition_by);
+        let mut index = 0;
+        let mut file_path = dir.join(format!("part-{index}.{ext}"));
+        while file_path.exists() {
+            index += 1;
+            file_path = dir.join(format!("part-{index}.{ext}"));
+        }
+        write_file(&data, &file_path, format)?;
+
+        let relative = file_path
+            .strip_prefix(out_dir)
+            .unwrap_or(&file_path)
+            .components()
+            .map(|part| part.as_os_str().to_string_lossy().to_string())
+            .collect::<Vec<_>>()
+            .join("/");
+        files.push(ManifestEntry {
+            path: relative,
+            row_count: data.height() as u64,
+            partition: values,
+        });
+    }
+
+    if write_manifest {
+        let mut manifest = ExportManifest {
+            format: format.to_string(),
+            partition_by: partition_by.to_vec(),
+            total_rows: df.height() as u64,
+            files,
+        };
+        if let Some(previous) = previous {
+            manifest.total_rows += previous.total_rows;
+            manifest.files.splice(0..0, previous.files);
+        }
+        let file = File::create(out_dir.join("_manifest.json")).map_err(|err| err.to_string())?;
+        serde_json::to_writer_pretty(file, &manifest).map_err(|err| err.to_string())?;
+    }
+
+    Ok(())
+}
+
+/// Writes `df` to `path` as a single file, a partitioned directory or a
+/// SQLite table, depending on `options`.
+pub(crate) fn export_frame(
+    df: DataFrame,
+    path: &Path,
+    options: &ExportOptions,
+) -> Result<(), Error> {
+    if options.format == "sqlite" {
+        if !options.partition_by.is_empty() {
+            return Err(Error::new(
+                ErrorCode::UnsupportedExport,
+                "Partitioned export is not supported for SQLite",
+            ));
+        }
+        let mode = WriteMode::parse(options.if_exists.as_deref())?;
+        let table = options.table_name.as_deref().unwrap_or("source");
+        return sqlite::write_table(&df, path, table, mode)
+            .map_err(|message| export_failed(path, message));
+    }
+
+    if options.partition_by.is_empty() {
+        write_export(&df, path, &options.format)
+    } else {
+        export_extension(&options.format)?;
+        let mode = WriteMode::parse(options.if_exists.as_deref())?;
+        export_partitioned(
+            df,
+            path,
+            &options.format,
+            &options.partition_by,
+            options.write_manifest,
+            mode,
+        )
+        .map_err(|message| export_failed(path, message))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn partition_values_follow_hive_naming() {
+        assert_eq!(
+            partition_dir_value(&AnyValue::Null),
+            "__HIVE_DEFAULT_PARTITION__"
+        );
+        assert_eq!(partition_dir_value(&AnyValue::Int64(-5)), "-5");
+        assert_eq!(partition_dir_value(&AnyValue::String("north")), "north");
+        assert_eq!(
+            partition_dir_value(&AnyValue::String("a/b=c: 50%")),
+            "a%2Fb%3Dc%3A 50%25"
+        );
+        assert_eq!(
+            partition_dir_value(&AnyValue::String("tab\there")),
+            "tab%09here"
+        );
+    }
+
+    #[test]
+    fn write_mode_defaults_to_replace() {
+        assert!(matches!(WriteMode::parse(None), Ok(WriteMode::Replace)));
+        assert!(matches!(
+            WriteMode::parse(Some("append")),
+            Ok(WriteMode::Append)
+        ));
+        assert!(WriteMode::parse(Some("merge")).is_err());
+    }
+}
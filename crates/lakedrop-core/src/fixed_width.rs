@@ -0,0 +1,261 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
+
+use flate2::read::GzDecoder;
+use polars::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct FixedWidthColumn {
+    pub name: String,
+    /// Zero-based character offset of the first character in the column.
+    pub start: usize,
+    pub width: usize,
+    /// Optional cast applied after trimming: `int`, `float`, `bool`, `date`,
+    /// `datetime` or `string`.
+    #[serde(default)]
+    pub dtype: Option<String>,
+}
+
+/// Column layout for fixed-width text. With no `columns`, the layout is
+/// inferred from a ruler line (`----- ---`) or from the header line.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct FixedWidthOptions {
+    #[serde(default)]
+    pub columns: Vec<FixedWidthColumn>,
+    /// Lines to drop before the data when `columns` are given explicitly,
+    /// e.g. a header row.
+    #[serde(default)]
+    pub skip_rows: usize,
+}
+
+fn read_lines(path: &Path, compressed: bool) -> Result<Vec<String>, String> {
+    let file = File::open(path).map_err(|err| err.to_string())?;
+    let reader: Box<dyn Read> = if compressed {
+        Box::new(GzDecoder::new(file))
+    } else {
+        Box::new(file)
+    };
+    BufReader::new(reader)
+        .lines()
+        .map(|line| {
+            line.map(|line| line.trim_end_matches('\r').to_string())
+                .map_err(|err| err.to_string())
+        })
+        .collect()
+}
+
+/// Runs of non-space characters as `(start, end)` character offsets.
+fn runs(line: &str, is_mark: impl Fn(char) -> bool) -> Vec<(usize, usize)> {
+    let mut spans = Vec::new();
+    let mut start = None;
+    for (idx, ch) in line.chars().enumerate() {
+        match (is_mark(ch), start) {
+            (true, None) => start = Some(idx),
+            (false, Some(begin)) => {
+                spans.push((begin, idx));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(begin) = start {
+        spans.push((begin, line.chars().count()));
+    }
+    spans
+}
+
+fn is_ruler(line: &str) -> bool {
+    let trimmed = line.trim();
+    !trimmed.is_empty()
+        && trimmed
+            .chars()
+            .all(|ch| matches!(ch, '-' | '=' | '+' | ' '))
+}
+
+fn slice_chars(line: &str, start: usize, width: usize) -> String {
+    line.chars()
+        .skip(start)
+        .take(width)
+        .collect::<String>()
+        .trim()
+        .to_string()
+}
+
+/// Infers columns from the first non-empty lines and returns them with the
+/// index of the first data line.
+fn infer_columns(lines: &[String]) -> Result<(Vec<FixedWidthColumn>, usize), String> {
+    let first = lines
+        .iter()
+        .position(|line| !line.trim().is_empty())
+        .ok_or("File is empty")?;
+
+    // A ruler either follows the header or is the very first line.
+    let (header, ruler, data_start) = if lines.get(first + 1).is_some_and(|line| is_ruler(line)) {
+        (Some(first), Some(first + 1), first + 2)
+    } else if is_ruler(&lines[first]) {
+        (None, Some(first), first + 1)
+    } else {
+        (Some(first), None, first + 1)
+    };
+
+    let spans = match ruler {
+        Some(ruler) => runs(&lines[ruler], |ch| ch != ' '),
+        None => {
+            // Each header word starts a column that runs up to the next; the
+            // last one takes the rest of the line.
+            let words = runs(&lines[first], |ch| !ch.is_whitespace());
+            let widest = lines
+                .iter()
+                .map(|line| line.chars().count())
+                .max()
+                .unwrap_or(0);
+            words
+                .iter()
+                .enumerate()
+                .map(|(idx, (start, end))| {
+                    let next = words.get(idx + 1).map(|(next, _)| *next);
+                    (*start, next.unwrap_or(widest.max(*end)))
+                })
+                .collect::<Vec<_>>()
+        }
+    };
+    if spans.is_empty() {
+        return Err("Could not infer fixed-width columns".to_string());
+    }
+
+    let columns = spans
+        .iter()
+        .enumerate()
+        .map(|(idx, (start, end))| {
+            let width = end.saturating_sub(*start);
+            let name = header
+                .map(|line| slice_chars(&lines[line], *start, width))
+                .filter(|name| !name.is_empty())
+                .unwrap_or_else(|| format!("col_{}", idx + 1));
+            FixedWidthColumn {
+                name,
+                start: *start,
+                width,
+                dtype: None,
+            }
+        })
+        .collect();
+    Ok((columns, data_start))
+}
+
+fn cast_target(dtype: &str) -> Result<Option<DataType>, String> {
+    Ok(match dtype.to_ascii_lowercase().as_str() {
+        "" | "string" | "str" | "text" => None,
+        "int" | "int64" | "integer" => Some(DataType::Int64),
+        "float" | "float64" | "double" => Some(DataType::Float64),
+        "bool" | "boolean" => Some(DataType::Boolean),
+        "date" => Some(DataType::Date),
+        "datetime" | "timestamp" => Some(DataType::Datetime(TimeUnit::Microseconds, None)),
+        other => return Err(format!("Unsupported fixed-width column type: {other}")),
+    })
+}
+
+/// Slices each line into trimmed string columns, then applies the per-column
+/// casts. Returns the frame and the resolved column layout.
+pub(crate) fn load_fixed_width(
+    path: &Path,
+    compressed: bool,
+    options: &FixedWidthOptions,
+) -> Result<(DataFrame, FixedWidthOptions), String> {
+    let lines = read_lines(path, compressed)?;
+    let (columns, data_start) = if options.columns.is_empty() {
+        infer_columns(&lines)?
+    } else {
+        (options.columns.clone(), options.skip_rows)
+    };
+
+    let mut values: Vec<Vec<Option<String>>> = vec![Vec::new(); columns.len()];
+    for line in lines.iter().skip(data_start) {
+        if line.trim().is_empty() {
+            continue;
+        }
+        for (column, target) in columns.iter().zip(values.iter_mut()) {
+            let value = slice_chars(line, column.start, column.width);
+            target.push((!value.is_empty()).then_some(value));
+        }
+    }
+
+    let series = columns
+        .iter()
+        .zip(values)
+        .map(|(column, values)| Series::new(&column.name, values))
+        .collect::<Vec<_>>();
+    let df = DataFrame::new(series).map_err(|err| err.to_string())?;
+
+    let mut casts = Vec::new();
+    for column in &columns {
+        if let Some(dtype) = cast_target(column.dtype.as_deref().unwrap_or(""))? {
+            casts.push(col(&column.name).cast(dtype));
+        }
+    }
+    let df = if casts.is_empty() {
+        df
+    } else {
+        df.lazy()
+            .with_columns(casts)
+            .collect()
+            .map_err(|err| err.to_string())?
+    };
+
+    Ok((
+        df,
+        FixedWidthOptions {
+            columns,
+            skip_rows: data_start,
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(text: &str) -> Vec<String> {
+        text.lines().map(str::to_string).collect()
+    }
+
+    fn layout(columns: &[FixedWidthColumn]) -> Vec<(&str, usize, usize)> {
+        columns
+            .iter()
+            .map(|column| (column.name.as_str(), column.start, column.width))
+            .collect()
+    }
+
+    #[test]
+    fn infers_columns_from_a_ruler() {
+        let (columns, data_start) =
+            infer_columns(&lines("name  age city\n----  --- ----\nAnn    34 Oslo")).unwrap();
+        assert_eq!(
+            layout(&columns),
+            vec![("name", 0, 4), ("age", 6, 3), ("city", 10, 4)]
+        );
+        assert_eq!(data_start, 2);
+    }
+
+    #[test]
+    fn names_columns_without_a_header() {
+        let (columns, data_start) = infer_columns(&lines("\n---- ---\nabcd xyz")).unwrap();
+        assert_eq!(layout(&columns), vec![("col_1", 0, 4), ("col_2", 5, 3)]);
+        assert_eq!(data_start, 2);
+    }
+
+    #[test]
+    fn infers_columns_from_header_words() {
+        let (columns, data_start) =
+            infer_columns(&lines("id  name\n1   Ann\n22  Bob Long")).unwrap();
+        assert_eq!(layout(&columns), vec![("id", 0, 4), ("name", 4, 8)]);
+        assert_eq!(data_start, 1);
+    }
+
+    #[test]
+    fn rejects_empty_input() {
+        assert!(infer_columns(&lines("\n  \n")).is_err());
+    }
+}
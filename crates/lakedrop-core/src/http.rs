@@ -0,0 +1,154 @@
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{detect_kind_from_name, FileKind};
+
+const PROGRESS_STEP: u64 = 256 * 1024;
+
+/// Validators saved next to each cached download.
+#[derive(Serialize, Deserialize)]
+struct CacheEntry {
+    url: String,
+    etag: Option<String>,
+    last_modified: Option<String>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct DownloadProgress {
+    pub url: String,
+    pub downloaded: u64,
+    pub total: Option<u64>,
+}
+
+pub fn is_http_url(path: &str) -> bool {
+    let lower = path.to_ascii_lowercase();
+    lower.starts_with("http://") || lower.starts_with("https://")
+}
+
+/// The path part of a URL, without query string or fragment, so the file
+/// extension can be detected.
+pub fn url_path(url: &str) -> &str {
+    url.split(['?', '#']).next().unwrap_or(url)
+}
+
+/// Stable across builds, unlike `DefaultHasher`, so cache names survive
+/// upgrades.
+pub fn fnv1a(value: &str) -> u64 {
+    value.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Parquet over HTTP is scanned in place with range requests; every other
+/// format has to be downloaded with [`fetch_cached`] before it can be opened.
+pub fn requires_download(url: &str) -> bool {
+    is_http_url(url)
+        && !detect_kind_from_name(Path::new(url_path(url)))
+            .is_ok_and(|spec| spec.kind == FileKind::Parquet && !spec.compressed)
+}
+
+fn cache_paths(cache_dir: &Path, url: &str) -> Result<(PathBuf, PathBuf), String> {
+    let dir = cache_dir.join("downloads");
+    std::fs::create_dir_all(&dir).map_err(|err| err.to_string())?;
+
+    let name = url_path(url)
+        .rsplit('/')
+        .find(|segment| !segment.is_empty())
+        .unwrap_or("download")
+        .chars()
+        .map(|ch| if ch.is_ascii_alphanumeric() || ".-_".contains(ch) { ch } else { '_' })
+        .collect::<String>();
+    let stem = format!("{:016x}-{name}", fnv1a(url));
+    Ok((dir.join(&stem), dir.join(format!("{stem}.cache.json"))))
+}
+
+fn read_entry(path: &PathBuf) -> Option<CacheEntry> {
+    let file = File::open(path).ok()?;
+    serde_json::from_reader(file).ok()
+}
+
+/// Downloads `url` below `cache_dir` and returns the local path.
+///
+/// A cached copy is revalidated with `If-None-Match` / `If-Modified-Since`
+/// and reused on `304 Not Modified`. `on_progress` is called while the body
+/// streams to disk, and once more with `total == downloaded` at the end.
+pub fn fetch_cached(
+    cache_dir: &Path,
+    url: &str,
+    mut on_progress: impl FnMut(&DownloadProgress),
+) -> Result<PathBuf, String> {
+    let (data_path, entry_path) = cache_paths(cache_dir, url)?;
+    let cached = if data_path.exists() {
+        read_entry(&entry_path)
+    } else {
+        None
+    };
+
+    let mut request = ureq::get(url);
+    if let Some(entry) = &cached {
+        if let Some(etag) = &entry.etag {
+            request = request.set("If-None-Match", etag);
+        }
+        if let Some(last_modified) = &entry.last_modified {
+            request = request.set("If-Modified-Since", last_modified);
+        }
+    }
+
+    let response = match request.call() {
+        Ok(response) => response,
+        // Offline or server trouble: an existing copy is better than nothing.
+        Err(ureq::Error::Transport(_)) if cached.is_some() => return Ok(data_path),
+        Err(err) => return Err(err.to_string()),
+    };
+    if response.status() == 304 && cached.is_some() {
+        return Ok(data_path);
+    }
+
+    let entry = CacheEntry {
+        url: url.to_string(),
+        etag: response.header("ETag").map(str::to_string),
+        last_modified: response.header("Last-Modified").map(str::to_string),
+    };
+    let total = response
+        .header("Content-Length")
+        .and_then(|value| value.parse::<u64>().ok());
+
+    let partial = data_path.with_extension("part");
+    let mut file = File::create(&partial).map_err(|err| err.to_string())?;
+    let mut reader = response.into_reader();
+    let mut buffer = vec![0u8; 64 * 1024];
+    let mut downloaded = 0u64;
+    let mut reported = 0u64;
+    loop {
+        let read = reader.read(&mut buffer).map_err(|err| err.to_string())?;
+        if read == 0 {
+            break;
+        }
+        file.write_all(&buffer[..read])
+            .map_err(|err| err.to_string())?;
+        downloaded += read as u64;
+        if downloaded - reported >= PROGRESS_STEP {
+            reported = downloaded;
+            on_progress(&DownloadProgress {
+                url: url.to_string(),
+                downloaded,
+                total,
+            });
+        }
+    }
+    file.flush().map_err(|err| err.to_string())?;
+    drop(file);
+    on_progress(&DownloadProgress {
+        url: url.to_string(),
+        downloaded,
+        total: Some(downloaded),
+    });
+
+    std::fs::rename(&partial, &data_path).map_err(|err| err.to_string())?;
+    let entry_file = File::create(&entry_path).map_err(|err| err.to_string())?;
+    serde_json::to_writer(entry_file, &entry).map_err(|err| err.to_string())?;
+    Ok(data_path)
+}
@@ -0,0 +1,418 @@
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+use apache_avro::types::Value as AvroValue;
+use parquet::file::reader::{FileReader, SerializedFileReader};
+use polars::prelude::*;
+use serde_json::Value;
+
+use crate::lakehouse::{percent_decode, scan_data_files, DataFile};
+use crate::TableVersion;
+
+pub(crate) struct IcebergSnapshot {
+    pub(crate) frame: LazyFrame,
+    pub(crate) snapshot_id: Option<i64>,
+    pub(crate) history: Vec<TableVersion>,
+}
+
+fn metadata_version(name: &str) -> Option<u64> {
+    let stem = name.strip_suffix(".metadata.json")?;
+    let digits = stem.strip_prefix('v').unwrap_or(stem);
+    let digits = digits.split('-').next()?;
+    digits.parse().ok()
+}
+
+fn metadata_files(root: &Path) -> Vec<(u64, PathBuf)> {
+    let Ok(entries) = std::fs::read_dir(root.join("metadata")) else {
+        return Vec::new();
+    };
+    let mut files = entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            metadata_version(&name).map(|version| (version, entry.path()))
+        })
+        .collect::<Vec<_>>();
+    files.sort_by_key(|(version, _)| *version);
+    files
+}
+
+pub(crate) fn is_iceberg_table(path: &Path) -> bool {
+    path.is_dir() && !metadata_files(path).is_empty()
+}
+
+/// Picks the table metadata file named by `version-hint.text`, falling back
+/// to the highest numbered `*.metadata.json`.
+fn current_metadata(root: &Path) -> Result<Value, String> {
+    let files = metadata_files(root);
+    let hinted = std::fs::read_to_string(root.join("metadata").join("version-hint.text"))
+        .ok()
+        .and_then(|hint| hint.trim().parse::<u64>().ok())
+        .and_then(|hint| files.iter().find(|(version, _)| *version == hint));
+    let (_, path) = hinted
+        .or_else(|| files.last())
+        .ok_or("Iceberg table has no metadata files")?;
+    let file = File::open(path).map_err(|err| err.to_string())?;
+    serde_json::from_reader(file).map_err(|err| err.to_string())
+}
+
+/// Maps a path recorded in table metadata onto the local copy of the table.
+/// Warehouses are often copied from elsewhere, so anything under the
+/// table's recorded `location` is re-rooted at `root`.
+fn resolve_path(root: &Path, location: Option<&str>, path: &str) -> PathBuf {
+    if let Some(relative) = location
+        .map(|location| location.trim_end_matches('/'))
+        .and_then(|location| path.strip_prefix(location))
+    {
+        return root.join(percent_decode(relative.trim_start_matches('/')));
+    }
+    let local = path
+        .strip_prefix("file://")
+        .or_else(|| path.strip_prefix("file:"))
+        .unwrap_or(path);
+    PathBuf::from(percent_decode(local))
+}
+
+fn iceberg_type(value: &Value) -> DataType {
+    match value.as_str() {
+        Some("boolean") => DataType::Boolean,
+        Some("int") => DataType::Int32,
+        Some("long") => DataType::Int64,
+        Some("float") => DataType::Float32,
+        Some("double") => DataType::Float64,
+        Some("date") => DataType::Date,
+        Some("time") => DataType::Time,
+        Some("timestamp") => DataType::Datetime(TimeUnit::Microseconds, None),
+        Some("timestamptz") => DataType::Datetime(TimeUnit::Microseconds, Some("UTC".to_string())),
+        Some("binary") => DataType::Binary,
+        Some(other) if other.starts_with("decimal") => DataType::Float64,
+        Some(other) if other.starts_with("fixed") => DataType::Binary,
+        _ => DataType::String,
+    }
+}
+
+/// The schema with `schema_id`, or the current one when that is `None`.
+fn table_schema(metadata: &Value, schema_id: Option<i64>) -> Option<&Value> {
+    let schema_id = schema_id.or_else(|| metadata.get("current-schema-id").and_then(Value::as_i64));
+    metadata
+        .get("schemas")
+        .and_then(Value::as_array)
+        .and_then(|schemas| {
+            schemas
+                .iter()
+                .find(|schema| schema.get("schema-id").and_then(Value::as_i64) == schema_id)
+        })
+        .or_else(|| metadata.get("schema"))
+}
+
+fn schema_fields(schema: Option<&Value>) -> Vec<(i64, String, DataType)> {
+    schema
+        .and_then(|schema| schema.get("fields"))
+        .and_then(Value::as_array)
+        .map(|fields| {
+            fields
+                .iter()
+                .filter_map(|field| {
+                    Some((
+                        field.get("id")?.as_i64()?,
+                        field.get("name")?.as_str()?.to_string(),
+                        iceberg_type(field.get("type")?),
+                    ))
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Identity partition fields of the spec with `spec_id` (the default spec
+/// when `None`) as (partition field name, source column). Other transforms
+/// (bucket, day, ...) never replace column values.
+fn identity_partitions(
+    metadata: &Value,
+    spec_id: Option<i64>,
+    fields: &[(i64, String, DataType)],
+) -> Vec<(String, String, DataType)> {
+    let spec_id = spec_id.or_else(|| metadata.get("default-spec-id").and_then(Value::as_i64));
+    let spec = metadata
+        .get("partition-specs")
+        .and_then(Value::as_array)
+        .and_then(|specs| {
+            specs
+                .iter()
+                .find(|spec| spec.get("spec-id").and_then(Value::as_i64) == spec_id)
+        })
+        .and_then(|spec| spec.get("fields"))
+        .or_else(|| metadata.get("partition-spec"))
+        .and_then(Value::as_array)
+        .cloned()
+        .unwrap_or_default();
+    spec.iter()
+        .filter(|field| field.get("transform").and_then(Value::as_str) == Some("identity"))
+        .filter_map(|field| {
+            let name = field.get("name")?.as_str()?.to_string();
+            let source_id = field.get("source-id")?.as_i64()?;
+            let (_, column, dtype) = fields.iter().find(|(id, _, _)| *id == source_id)?;
+            Some((name, column.clone(), dtype.clone()))
+        })
+        .collect()
+}
+
+fn history(metadata: &Value) -> Vec<TableVersion> {
+    let mut versions = metadata
+        .get("snapshots")
+        .and_then(Value::as_array)
+        .map(|snapshots| {
+            snapshots
+                .iter()
+                .filter_map(|snapshot| {
+                    Some(TableVersion {
+                        id: snapshot.get("snapshot-id")?.as_i64()?.to_string(),
+                        timestamp_ms: snapshot.get("timestamp-ms").and_then(Value::as_i64),
+                        operation: snapshot
+                            .get("summary")
+                            .and_then(|summary| summary.get("operation"))
+                            .and_then(Value::as_str)
+                            .map(str::to_string),
+                    })
+                })
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    versions.sort_by_key(|version| std::cmp::Reverse(version.timestamp_ms));
+    versions
+}
+
+fn unwrap_union(value: &AvroValue) -> &AvroValue {
+    match value {
+        AvroValue::Union(_, inner) => inner,
+        other => other,
+    }
+}
+
+fn record_field<'a>(value: &'a AvroValue, name: &str) -> Option<&'a AvroValue> {
+    match unwrap_union(value) {
+        AvroValue::Record(fields) => fields
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| unwrap_union(value)),
+        _ => None,
+    }
+}
+
+fn avro_string(value: Option<&AvroValue>) -> Option<String> {
+    match value? {
+        AvroValue::String(value) => Some(value.clone()),
+        _ => None,
+    }
+}
+
+fn avro_long(value: Option<&AvroValue>) -> Option<i64> {
+    match value? {
+        AvroValue::Int(value) => Some(*value as i64),
+        AvroValue::Long(value) => Some(*value),
+        _ => None,
+    }
+}
+
+fn read_avro(path: &Path) -> Result<Vec<AvroValue>, String> {
+    let file = File::open(path).map_err(|err| err.to_string())?;
+    let reader = apache_avro::Reader::new(file).map_err(|err| err.to_string())?;
+    reader
+        .map(|value| value.map_err(|err| err.to_string()))
+        .collect()
+}
+
+/// Converts days since the Unix epoch into a `YYYY-MM-DD` string.
+fn format_date(days: i32) -> String {
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+/// Renders an identity partition value as text that casts back to the
+/// column type. `None` means the value has no such rendering and the column
+/// is left as stored in the data file.
+fn partition_literal(value: &AvroValue) -> Option<Option<String>> {
+    match unwrap_union(value) {
+        AvroValue::Null => Some(None),
+        AvroValue::Boolean(value) => Some(Some(value.to_string())),
+        AvroValue::Int(value) => Some(Some(value.to_string())),
+        AvroValue::Long(value) => Some(Some(value.to_string())),
+        AvroValue::Float(value) => Some(Some(value.to_string())),
+        AvroValue::Double(value) => Some(Some(value.to_string())),
+        AvroValue::String(value) => Some(Some(value.clone())),
+        AvroValue::Date(value) => Some(Some(format_date(*value))),
+        _ => None,
+    }
+}
+
+/// Finds the table columns in a data file by the Iceberg field ids written
+/// into its Parquet schema, as (table column, column in the file). Renamed
+/// columns are found under the name they were written with, and a column
+/// dropped and re-added under the same name does not pick up the old data.
+/// Files without field ids (imported from plain Parquet) give `None` and are
+/// matched by name.
+fn file_columns(
+    path: &Path,
+    fields: &[(i64, String, DataType)],
+) -> Result<Option<Vec<(String, String)>>, String> {
+    let file = File::open(path).map_err(|err| format!("{}: {err}", path.display()))?;
+    let reader = SerializedFileReader::new(file).map_err(|err| err.to_string())?;
+    let ids = reader
+        .metadata()
+        .file_metadata()
+        .schema_descr()
+        .root_schema()
+        .get_fields()
+        .iter()
+        .filter(|field| field.get_basic_info().has_id())
+        .map(|field| (field.get_basic_info().id() as i64, field.name().to_string()))
+        .collect::<Vec<_>>();
+    if ids.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(
+        fields
+            .iter()
+            .filter_map(|(id, name, _)| {
+                let (_, source) = ids.iter().find(|(file_id, _)| file_id == id)?;
+                Some((name.clone(), source.clone()))
+            })
+            .collect(),
+    ))
+}
+
+fn manifest_data_files(
+    root: &Path,
+    location: Option<&str>,
+    manifest_path: &Path,
+    fields: &[(i64, String, DataType)],
+    partitions: &[(String, String, DataType)],
+) -> Result<Vec<DataFile>, String> {
+    let mut files = Vec::new();
+    for entry in read_avro(manifest_path)? {
+        // status 2 marks files deleted by this snapshot
+        if avro_long(record_field(&entry, "status")) == Some(2) {
+            continue;
+        }
+        let Some(data_file) = record_field(&entry, "data_file") else {
+            continue;
+        };
+        if avro_long(record_field(data_file, "content")).unwrap_or(0) != 0 {
+            return Err("Iceberg delete files are not supported yet".to_string());
+        }
+        let format = avro_string(record_field(data_file, "file_format")).unwrap_or_default();
+        if !format.eq_ignore_ascii_case("parquet") {
+            return Err(format!("Iceberg data files in {format} format are not supported"));
+        }
+        let path = avro_string(record_field(data_file, "file_path"))
+            .ok_or("Iceberg manifest entry is missing file_path")?;
+        let partition = record_field(data_file, "partition");
+        let partition_values = partitions
+            .iter()
+            .filter_map(|(field, column, _)| {
+                let value = partition.and_then(|partition| record_field(partition, field))?;
+                Some((column.clone(), partition_literal(value)?))
+            })
+            .collect();
+        let path = resolve_path(root, location, &path);
+        files.push(DataFile {
+            columns: file_columns(&path, fields)?,
+            path,
+            partition_values,
+        });
+    }
+    Ok(files)
+}
+
+fn polars_schema(fields: &[(i64, String, DataType)]) -> Schema {
+    fields
+        .iter()
+        .map(|(_, name, dtype)| Field::new(name, dtype.clone()))
+        .collect()
+}
+
+/// Resolves the data files of a snapshot (the current one when `None`) from
+/// its manifest list and scans them as a single `LazyFrame`. The snapshot is
+/// read with the schema it was written with, and each manifest with the
+/// partition spec it was written with, so older snapshots keep working after
+/// the table evolves. Data files are matched to that schema by field id.
+pub(crate) fn load_iceberg(root: &Path, snapshot_id: Option<i64>) -> Result<IcebergSnapshot, String> {
+    let metadata = current_metadata(root)?;
+    let location = metadata.get("location").and_then(Value::as_str);
+
+    let target = snapshot_id.or_else(|| {
+        metadata
+            .get("current-snapshot-id")
+            .and_then(Value::as_i64)
+            .filter(|id| *id >= 0)
+    });
+    let Some(target) = target else {
+        let schema = polars_schema(&schema_fields(table_schema(&metadata, None)));
+        return Ok(IcebergSnapshot {
+            frame: DataFrame::empty_with_schema(&schema).lazy(),
+            snapshot_id: None,
+            history: history(&metadata),
+        });
+    };
+    let snapshot = metadata
+        .get("snapshots")
+        .and_then(Value::as_array)
+        .and_then(|snapshots| {
+            snapshots
+                .iter()
+                .find(|snapshot| snapshot.get("snapshot-id").and_then(Value::as_i64) == Some(target))
+        })
+        .ok_or_else(|| format!("Iceberg snapshot {target} does not exist"))?;
+    // Snapshots written before format v2 carry no schema-id.
+    let schema_id = snapshot.get("schema-id").and_then(Value::as_i64);
+    let fields = schema_fields(table_schema(&metadata, schema_id));
+    let schema = polars_schema(&fields);
+
+    // (manifest path, partition spec id); v1 manifest lists may leave the
+    // spec id out, and a plain `manifests` array never has it.
+    let mut manifests: Vec<(String, Option<i64>)> = Vec::new();
+    if let Some(list) = snapshot.get("manifest-list").and_then(Value::as_str) {
+        for entry in read_avro(&resolve_path(root, location, list))? {
+            if avro_long(record_field(&entry, "content")).unwrap_or(0) != 0 {
+                return Err("Iceberg delete files are not supported yet".to_string());
+            }
+            if let Some(path) = avro_string(record_field(&entry, "manifest_path")) {
+                let spec_id = avro_long(record_field(&entry, "partition_spec_id"));
+                manifests.push((path, spec_id));
+            }
+        }
+    } else if let Some(paths) = snapshot.get("manifests").and_then(Value::as_array) {
+        manifests.extend(
+            paths
+                .iter()
+                .filter_map(Value::as_str)
+                .map(|path| (path.to_string(), None)),
+        );
+    }
+
+    let mut files = Vec::new();
+    for (manifest, spec_id) in manifests {
+        let partitions = identity_partitions(&metadata, spec_id, &fields);
+        files.extend(manifest_data_files(
+            root,
+            location,
+            &resolve_path(root, location, &manifest),
+            &fields,
+            &partitions,
+        )?);
+    }
+
+    Ok(IcebergSnapshot {
+        frame: scan_data_files(&files, &schema)?,
+        snapshot_id: Some(target),
+        history: history(&metadata),
+    })
+}
@@ -0,0 +1,154 @@
+use std::path::PathBuf;
+
+use polars::prelude::*;
+
+/// One Parquet data file of a table snapshot, with the partition values that
+/// the table format keeps in its metadata rather than in the file itself.
+pub(crate) struct DataFile {
+    pub(crate) path: PathBuf,
+    pub(crate) partition_values: Vec<(String, Option<String>)>,
+    /// (table column, column in the file) for formats that track columns by
+    /// id, so renamed columns are found under the name they were written
+    /// with. `None` matches columns by name.
+    pub(crate) columns: Option<Vec<(String, String)>>,
+}
+
+/// Scans the data files of a snapshot as one `LazyFrame` with exactly the
+/// columns of `table_schema`. Each file is projected onto the table schema:
+/// partition columns a file has values for become typed literals, columns
+/// the file lacks come back as nulls, columns the table no longer has are
+/// left out, and the rest are cast to the table type.
+pub(crate) fn scan_data_files(
+    files: &[DataFile],
+    table_schema: &Schema,
+) -> Result<LazyFrame, String> {
+    if files.is_empty() {
+        return Ok(DataFrame::empty_with_schema(table_schema).lazy());
+    }
+
+    let mut frames = Vec::with_capacity(files.len());
+    for file in files {
+        let lf = LazyFrame::scan_parquet(&file.path, ScanArgsParquet::default())
+            .map_err(|err| err.to_string())?;
+        let file_schema = lf.schema().map_err(|err| err.to_string())?;
+        let columns = table_schema
+            .iter()
+            .map(|(name, dtype)| {
+                let name = name.as_str();
+                let partition = file.partition_values.iter().find(|(key, _)| key == name);
+                if let Some((_, value)) = partition {
+                    let expr = match value {
+                        Some(value) => lit(value.clone()),
+                        None => lit(Null {}),
+                    };
+                    return expr.cast(dtype.clone()).alias(name);
+                }
+                let source = match &file.columns {
+                    Some(columns) => columns
+                        .iter()
+                        .find(|(column, _)| column == name)
+                        .map(|(_, source)| source.as_str()),
+                    None => Some(name),
+                };
+                match source.filter(|source| file_schema.contains(source)) {
+                    // String also stands in for nested types that are not
+                    // mapped, which must stay as stored.
+                    Some(source) if *dtype == DataType::String => col(source).alias(name),
+                    Some(source) => col(source).cast(dtype.clone()).alias(name),
+                    None => lit(Null {}).cast(dtype.clone()).alias(name),
+                }
+            })
+            .collect::<Vec<_>>();
+        frames.push(lf.select(columns));
+    }
+
+    concat(frames, UnionArgs::default()).map_err(|err| err.to_string())
+}
+
+/// Decodes the `%XX` escapes of table log paths and URL path segments.
+pub fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut idx = 0;
+    while idx < bytes.len() {
+        if bytes[idx] == b'%' && idx + 2 < bytes.len() {
+            let byte = std::str::from_utf8(&bytes[idx + 1..idx + 3])
+                .ok()
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+            if let Some(byte) = byte {
+                decoded.push(byte);
+                idx += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[idx]);
+        idx += 1;
+    }
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::path::Path;
+
+    use polars::prelude::*;
+
+    use super::{scan_data_files, DataFile};
+
+    fn write(path: &Path, mut df: DataFrame) {
+        ParquetWriter::new(File::create(path).unwrap())
+            .finish(&mut df)
+            .unwrap();
+    }
+
+    #[test]
+    fn projects_files_onto_the_table_schema() {
+        let dir = std::env::temp_dir().join(format!("lakedrop-lakehouse-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        // Written before `name` was renamed to `label`, `legacy` was dropped,
+        // `id` was widened to a long and `score` was added.
+        let old = dir.join("old.parquet");
+        write(
+            &old,
+            df!("id" => [1i32], "name" => ["a"], "legacy" => ["x"]).unwrap(),
+        );
+        let new = dir.join("new.parquet");
+        write(
+            &new,
+            df!("id" => [2i64], "label" => ["b"], "score" => [0.5]).unwrap(),
+        );
+        let files = [
+            DataFile {
+                path: old,
+                partition_values: vec![("region".to_string(), Some("north".to_string()))],
+                columns: Some(vec![
+                    ("id".to_string(), "id".to_string()),
+                    ("label".to_string(), "name".to_string()),
+                ]),
+            },
+            DataFile {
+                path: new,
+                partition_values: vec![("region".to_string(), None)],
+                columns: None,
+            },
+        ];
+        let schema = Schema::from_iter([
+            Field::new("id", DataType::Int64),
+            Field::new("label", DataType::String),
+            Field::new("score", DataType::Float64),
+            Field::new("region", DataType::String),
+        ]);
+
+        let df = scan_data_files(&files, &schema).unwrap().collect().unwrap();
+        assert_eq!(df.schema(), schema);
+        let expected = df!(
+            "id" => [1i64, 2],
+            "label" => ["a", "b"],
+            "score" => [None, Some(0.5)],
+            "region" => [Some("north"), None]
+        )
+        .unwrap();
+        assert!(df.equals_missing(&expected));
+    }
+}
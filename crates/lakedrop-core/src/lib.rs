@@ -0,0 +1,572 @@
+//! File detection, loading, SQL and export behind LakeDrop, without any UI.
+//!
+//! A [`Session`] holds one open source plus any extra tables registered next
+//! to it; the desktop app keeps one per window and the CLI one per run.
+
+mod archive;
+mod avro;
+mod delta;
+mod error;
+pub mod excel;
+mod export;
+pub mod fixed_width;
+pub mod http;
+mod iceberg;
+mod lakehouse;
+mod orc;
+pub mod s3;
+mod session;
+mod sql;
+mod sql_format;
+pub mod sqlite;
+
+use std::fs::File;
+use std::io::{Cursor, Read};
+use std::path::Path;
+
+use flate2::read::GzDecoder;
+use polars::lazy::dsl::col;
+use polars::prelude::*;
+use serde::{Deserialize, Serialize};
+
+pub use error::{Error, ErrorCode, SqlSpan};
+pub use export::{export_extension, write_export, ExportOptions};
+pub use lakehouse::percent_decode;
+pub use session::Session;
+pub use sql::{Completion, CompletionKind};
+pub use sql_format::{format_sql, FormatOptions, KeywordCase};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FileKind {
+    Parquet,
+    Csv,
+    JsonLines,
+    Json,
+    Arrow,
+    Excel,
+    Sqlite,
+    Avro,
+    Orc,
+    Delta,
+    Iceberg,
+    Archive,
+    FixedWidth,
+}
+
+pub struct FileSpec {
+    pub kind: FileKind,
+    pub compressed: bool,
+    pub extension: String,
+}
+
+#[derive(Clone, Serialize)]
+pub struct FieldInfo {
+    pub name: String,
+    pub dtype: String,
+}
+
+#[derive(Clone, Serialize)]
+pub struct BlockInfo {
+    pub row_count: u64,
+    pub byte_size: u64,
+}
+
+/// Physical layout of columnar files: Parquet row groups or ORC stripes.
+#[derive(Clone, Serialize)]
+pub struct StorageInfo {
+    pub format: String,
+    pub block_kind: String,
+    pub compression: Vec<String>,
+    pub blocks: Vec<BlockInfo>,
+}
+
+/// One entry of a table format's version history, newest first. Ids are
+/// strings because Iceberg snapshot ids do not survive a round trip through
+/// JavaScript numbers.
+#[derive(Clone, Serialize)]
+pub struct TableVersion {
+    pub id: String,
+    pub timestamp_ms: Option<i64>,
+    pub operation: Option<String>,
+}
+
+/// What a [`Session`] reports after opening a file or changing its selection.
+#[derive(Clone, Serialize)]
+pub struct FileMetadata {
+    pub file_name: String,
+    pub file_path: String,
+    pub file_size: u64,
+    pub file_kind: FileKind,
+    pub row_count: u64,
+    pub schema: Vec<FieldInfo>,
+    pub sheets: Vec<String>,
+    pub active_sheet: Option<String>,
+    pub ranges: Vec<excel::WorkbookRange>,
+    pub tables: Vec<String>,
+    pub storage: Option<StorageInfo>,
+    pub versions: Vec<TableVersion>,
+    pub active_version: Option<String>,
+    pub fixed_width: Option<fixed_width::FixedWidthOptions>,
+}
+
+/// A table SQL can refer to: `source` or one registered next to it.
+#[derive(Clone, Serialize)]
+pub struct TableInfo {
+    pub name: String,
+    pub schema: Vec<FieldInfo>,
+}
+
+#[derive(Serialize)]
+pub struct ColumnInfo {
+    pub name: String,
+    pub dtype: String,
+}
+
+#[derive(Serialize)]
+pub struct QueryResult {
+    pub columns: Vec<ColumnInfo>,
+    pub rows: Vec<Vec<serde_json::Value>>,
+    pub row_count: usize,
+    /// Rows the query produced before `max_rows` was applied.
+    pub total_rows: usize,
+}
+
+impl QueryResult {
+    /// Converts at most `max_rows` rows of a collected result to JSON values.
+    pub fn from_frame(df: DataFrame, max_rows: Option<usize>) -> Result<Self, String> {
+        let total_rows = df.height();
+
+        let df = if let Some(max_rows) = max_rows {
+            df.head(Some(max_rows))
+        } else {
+            df
+        };
+
+        let columns = df
+            .schema()
+            .iter_fields()
+            .map(|field| ColumnInfo {
+                name: field.name().to_string(),
+                dtype: format!("{:?}", field.data_type()),
+            })
+            .collect::<Vec<_>>();
+
+        let row_count = df.height();
+        let mut rows = Vec::with_capacity(row_count);
+        for row_idx in 0..row_count {
+            let mut row = Vec::with_capacity(df.width());
+            for series in df.get_columns() {
+                let value = series.get(row_idx).map_err(|err| err.to_string())?;
+                row.push(any_value_to_json(value));
+            }
+            rows.push(row);
+        }
+
+        Ok(QueryResult {
+            columns,
+            rows,
+            row_count,
+            total_rows,
+        })
+    }
+}
+
+fn gzip_magic(path: &Path) -> Result<bool, String> {
+    let mut file = File::open(path).map_err(|err| err.to_string())?;
+    let mut buf = [0u8; 2];
+    let read = file.read(&mut buf).map_err(|err| err.to_string())?;
+    Ok(read == 2 && buf == [0x1f, 0x8b])
+}
+
+fn unsupported_format(path: &Path, ext: &str) -> Error {
+    Error::new(
+        ErrorCode::UnsupportedFormat,
+        format!("Unsupported file type: .{ext}"),
+    )
+    .with_path(path)
+    .with_hint(
+        "Open Parquet, CSV/TSV, JSON, Arrow, Excel, SQLite, Avro, ORC, fixed-width text, \
+         an archive, or a Delta/Iceberg table directory.",
+    )
+}
+
+/// The lowercase extension, looking through a trailing `.gz`, and whether
+/// that `.gz` was there.
+fn split_extension(path: &Path) -> (String, bool) {
+    let extension = |path: &Path| {
+        path.extension()
+            .and_then(|value| value.to_str())
+            .unwrap_or("")
+            .to_ascii_lowercase()
+    };
+    let ext = extension(path);
+    if ext != "gz" {
+        return (ext, false);
+    }
+    let inner = path
+        .file_stem()
+        .map(|stem| extension(Path::new(stem)))
+        .unwrap_or_default();
+    (inner, true)
+}
+
+/// SQLite is left out: `.db` is used for much else, so databases are
+/// recognised by their header when there is a file to read.
+fn kind_for_extension(ext: &str) -> Option<FileKind> {
+    let kind = match ext {
+        "parquet" | "parq" => FileKind::Parquet,
+        "csv" | "tsv" | "txt" => FileKind::Csv,
+        "jsonl" | "ndjson" => FileKind::JsonLines,
+        "json" => FileKind::Json,
+        "arrow" | "feather" | "ipc" => FileKind::Arrow,
+        "xlsx" | "xlsm" | "xls" | "xlsb" | "ods" => FileKind::Excel,
+        "avro" => FileKind::Avro,
+        "orc" => FileKind::Orc,
+        "zip" | "tar" | "tgz" => FileKind::Archive,
+        "dat" | "fwf" => FileKind::FixedWidth,
+        _ => return None,
+    };
+    Some(kind)
+}
+
+/// Classifies a path by its name alone, for archive members and URLs where
+/// nothing local should be probed. SQLite goes by its usual extensions here,
+/// and table directories are never recognised.
+pub fn detect_kind_from_name(path: &Path) -> Result<FileSpec, Error> {
+    let (ext, gz) = split_extension(path);
+    let kind = match ext.as_str() {
+        "db" | "sqlite" | "sqlite3" => FileKind::Sqlite,
+        other => kind_for_extension(other).ok_or_else(|| unsupported_format(path, &ext))?,
+    };
+    Ok(FileSpec {
+        kind,
+        compressed: gz || ext == "tgz",
+        extension: ext,
+    })
+}
+
+/// Probes the filesystem for table directories, the SQLite header and gzip
+/// magic, falling back to the extension.
+pub fn detect_file_kind(path: &Path) -> Result<FileSpec, Error> {
+    if delta::is_delta_table(path) {
+        return Ok(FileSpec {
+            kind: FileKind::Delta,
+            compressed: false,
+            extension: String::new(),
+        });
+    }
+    if iceberg::is_iceberg_table(path) {
+        return Ok(FileSpec {
+            kind: FileKind::Iceberg,
+            compressed: false,
+            extension: String::new(),
+        });
+    }
+
+    let (ext, mut compressed) = split_extension(path);
+
+    if sqlite::sqlite_magic(path).unwrap_or(false) {
+        return Ok(FileSpec {
+            kind: FileKind::Sqlite,
+            compressed: false,
+            extension: ext,
+        });
+    }
+
+    let kind = kind_for_extension(&ext).ok_or_else(|| unsupported_format(path, &ext))?;
+
+    if !compressed {
+        compressed = gzip_magic(path).unwrap_or(false);
+    }
+
+    Ok(FileSpec {
+        kind,
+        compressed,
+        extension: ext,
+    })
+}
+
+pub fn schema_to_fields(schema: &Schema) -> Vec<FieldInfo> {
+    schema
+        .iter()
+        .map(|(name, dtype)| FieldInfo {
+            name: name.to_string(),
+            dtype: format!("{dtype:?}"),
+        })
+        .collect()
+}
+
+/// Only text formats, archives and fixed-width files are read through gzip.
+fn check_compression(path: &Path, spec: &FileSpec) -> Result<(), Error> {
+    let readable = matches!(
+        spec.kind,
+        FileKind::Csv
+            | FileKind::JsonLines
+            | FileKind::Json
+            | FileKind::Archive
+            | FileKind::FixedWidth
+    );
+    if spec.compressed && !readable {
+        return Err(Error::new(
+            ErrorCode::UnsupportedCompression,
+            "Compressed file is not supported for this format",
+        )
+        .with_path(path)
+        .with_hint("Decompress the file first; gzip is only read for text formats."));
+    }
+    Ok(())
+}
+
+/// Loads a local file with default options: the first sheet, table or
+/// archive member, the latest table version and an inferred fixed-width
+/// layout.
+pub fn load_lazy_frame(path: &Path, spec: &FileSpec) -> Result<LazyFrame, Error> {
+    if !path.exists() {
+        return Err(Error::file_not_found(path));
+    }
+    check_compression(path, spec)?;
+    read_lazy_frame(path, spec)
+        .map_err(|message| Error::new(ErrorCode::ReadFailed, message).with_path(path))
+}
+
+fn read_lazy_frame(path: &Path, spec: &FileSpec) -> Result<LazyFrame, String> {
+    match (spec.kind, spec.compressed) {
+        (FileKind::Parquet, false) => {
+            LazyFrame::scan_parquet(path, ScanArgsParquet::default()).map_err(|err| err.to_string())
+        }
+        (FileKind::Csv, false) => LazyCsvReader::new(path)
+            .with_separator(if spec.extension == "tsv" { b'\t' } else { b',' })
+            .with_try_parse_dates(true)
+            .finish()
+            .map_err(|err| err.to_string()),
+        (FileKind::JsonLines, false) => {
+            let file = File::open(path).map_err(|err| err.to_string())?;
+            let df = JsonLineReader::new(file)
+                .finish()
+                .map_err(|err| err.to_string())?;
+            Ok(df.lazy())
+        }
+        (FileKind::Json, false) => {
+            let file = File::open(path).map_err(|err| err.to_string())?;
+            let df = JsonReader::new(file)
+                .with_json_format(JsonFormat::Json)
+                .finish()
+                .map_err(|err| err.to_string())?;
+            Ok(df.lazy())
+        }
+        (FileKind::Arrow, false) => {
+            let file = File::open(path).map_err(|err| err.to_string())?;
+            let df = IpcReader::new(file)
+                .finish()
+                .map_err(|err| err.to_string())?;
+            Ok(df.lazy())
+        }
+        (FileKind::Avro, false) => Ok(avro::load_avro(path)?.lazy()),
+        (FileKind::Delta, false) => Ok(delta::load_delta(path, None)?.frame),
+        (FileKind::Iceberg, false) => Ok(iceberg::load_iceberg(path, None)?.frame),
+        (FileKind::Orc, false) => {
+            let df = orc::load_orc(path)?;
+            Ok(df.lazy())
+        }
+        (FileKind::Excel, false) => {
+            let workbook = excel::load_excel_sheet(path, None)?;
+            Ok(workbook.frame.lazy())
+        }
+        (FileKind::Sqlite, false) => {
+            let (df, _, _) = sqlite::load_sqlite_table(path, None)?;
+            Ok(df.lazy())
+        }
+        (FileKind::Csv, true) => {
+            let file = File::open(path).map_err(|err| err.to_string())?;
+            let mut decoder = GzDecoder::new(file);
+            let mut buffer = Vec::new();
+            decoder
+                .read_to_end(&mut buffer)
+                .map_err(|err| err.to_string())?;
+            let cursor = Cursor::new(buffer);
+            let reader = CsvReadOptions::default()
+                .map_parse_options(|options: CsvParseOptions| {
+                    options
+                        .with_separator(if spec.extension == "tsv" { b'\t' } else { b',' })
+                        .with_try_parse_dates(true)
+                })
+                .into_reader_with_file_handle(cursor);
+            let df = reader.finish().map_err(|err| err.to_string())?;
+            Ok(df.lazy())
+        }
+        (FileKind::JsonLines, true) => {
+            let file = File::open(path).map_err(|err| err.to_string())?;
+            let mut decoder = GzDecoder::new(file);
+            let mut buffer = Vec::new();
+            decoder
+                .read_to_end(&mut buffer)
+                .map_err(|err| err.to_string())?;
+            let cursor = Cursor::new(buffer);
+            let df = JsonLineReader::new(cursor)
+                .finish()
+                .map_err(|err| err.to_string())?;
+            Ok(df.lazy())
+        }
+        (FileKind::Json, true) => {
+            let file = File::open(path).map_err(|err| err.to_string())?;
+            let mut decoder = GzDecoder::new(file);
+            let mut buffer = Vec::new();
+            decoder
+                .read_to_end(&mut buffer)
+                .map_err(|err| err.to_string())?;
+            let cursor = Cursor::new(buffer);
+            let df = JsonReader::new(cursor)
+                .with_json_format(JsonFormat::Json)
+                .finish()
+                .map_err(|err| err.to_string())?;
+            Ok(df.lazy())
+        }
+        (FileKind::Archive, _) => {
+            let (lf, _, _) = load_archive_member(path, spec, None)?;
+            Ok(lf)
+        }
+        (FileKind::FixedWidth, compressed) => {
+            let options = fixed_width::FixedWidthOptions::default();
+            let (df, _) = fixed_width::load_fixed_width(path, compressed, &options)?;
+            Ok(df.lazy())
+        }
+        (_, true) => Err("Compressed file is not supported for this format".to_string()),
+    }
+}
+
+/// Loads one member of a ZIP or tar archive, defaulting to the first data file.
+/// Returns the frame, every loadable member, and the member that was loaded.
+fn load_archive_member(
+    path: &Path,
+    spec: &FileSpec,
+    member: Option<String>,
+) -> Result<(LazyFrame, Vec<String>, String), String> {
+    let members = archive::list_members(path, &spec.extension, spec.compressed)?;
+    let active = member
+        .or_else(|| members.first().cloned())
+        .ok_or("No supported data files found in archive")?;
+    if !members.contains(&active) {
+        return Err(format!("Archive member not found: {active}"));
+    }
+    let extracted = archive::extract_member(path, &spec.extension, spec.compressed, &active)?;
+    let member_spec = detect_file_kind(&extracted).map_err(|err| err.message)?;
+    let lf = load_lazy_frame(&extracted, &member_spec).map_err(|err| err.message)?;
+    Ok((lf, members, active))
+}
+
+fn parquet_storage_info(path: &Path) -> Result<StorageInfo, String> {
+    let file = File::open(path).map_err(|err| err.to_string())?;
+    let mut reader = ParquetReader::new(file);
+    let metadata = reader.get_metadata().map_err(|err| err.to_string())?;
+
+    let mut compression = Vec::new();
+    let mut blocks = Vec::with_capacity(metadata.row_groups.len());
+    for row_group in &metadata.row_groups {
+        for column in row_group.columns() {
+            let codec = format!("{:?}", column.compression());
+            if !compression.contains(&codec) {
+                compression.push(codec);
+            }
+        }
+        blocks.push(BlockInfo {
+            row_count: row_group.num_rows() as u64,
+            byte_size: row_group.compressed_size() as u64,
+        });
+    }
+
+    Ok(StorageInfo {
+        format: "parquet".to_string(),
+        block_kind: "row_group".to_string(),
+        compression,
+        blocks,
+    })
+}
+
+fn storage_info(path: &Path, spec: &FileSpec) -> Option<StorageInfo> {
+    match (spec.kind, spec.compressed) {
+        (FileKind::Parquet, false) => parquet_storage_info(path).ok(),
+        (FileKind::Orc, false) => orc::orc_storage_info(path).ok(),
+        _ => None,
+    }
+}
+
+pub fn lazy_row_count(lf: &LazyFrame) -> Result<u64, String> {
+    let df = lf
+        .clone()
+        .select([col("*").len()])
+        .collect()
+        .map_err(|err| err.to_string())?;
+    let series = df.get_columns().get(0).ok_or("Missing count")?;
+    let value = series.get(0).map_err(|err| err.to_string())?;
+    let count = match value {
+        AnyValue::UInt64(value) => value,
+        AnyValue::UInt32(value) => value as u64,
+        AnyValue::Int64(value) => value as u64,
+        AnyValue::Int32(value) => value as u64,
+        _ => 0,
+    };
+    Ok(count)
+}
+
+pub fn any_value_to_json(value: AnyValue) -> serde_json::Value {
+    match value {
+        AnyValue::Null => serde_json::Value::Null,
+        AnyValue::Boolean(value) => serde_json::Value::Bool(value),
+        AnyValue::Int8(value) => serde_json::Value::from(value),
+        AnyValue::Int16(value) => serde_json::Value::from(value),
+        AnyValue::Int32(value) => serde_json::Value::from(value),
+        AnyValue::Int64(value) => serde_json::Value::from(value),
+        AnyValue::UInt8(value) => serde_json::Value::from(value),
+        AnyValue::UInt16(value) => serde_json::Value::from(value),
+        AnyValue::UInt32(value) => serde_json::Value::from(value),
+        AnyValue::UInt64(value) => serde_json::Value::from(value),
+        AnyValue::Float32(value) => serde_json::Value::from(value as f64),
+        AnyValue::Float64(value) => serde_json::Value::from(value),
+        AnyValue::String(value) => serde_json::Value::String(value.to_string()),
+        AnyValue::StringOwned(value) => serde_json::Value::String(value.to_string()),
+        AnyValue::Binary(value) => serde_json::Value::String(format!("{value:?}")),
+        AnyValue::BinaryOwned(value) => serde_json::Value::String(format!("{value:?}")),
+        AnyValue::Date(value) => serde_json::Value::String(value.to_string()),
+        AnyValue::Datetime(value, _, _) => serde_json::Value::String(value.to_string()),
+        AnyValue::Time(value) => serde_json::Value::String(value.to_string()),
+        AnyValue::Duration(value, _) => serde_json::Value::String(value.to_string()),
+        AnyValue::List(_) => serde_json::Value::String(value.to_string()),
+        _ => serde_json::Value::String(value.to_string()),
+    }
+}
+
+/// Loads a Delta version or Iceberg snapshot (the latest when `version` is
+/// `None`), returning the frame, the history and the version actually loaded.
+fn load_table_version(
+    path: &Path,
+    kind: FileKind,
+    version: Option<&str>,
+) -> Result<(LazyFrame, Vec<TableVersion>, Option<String>), String> {
+    let version = version
+        .map(|value| {
+            value
+                .parse::<i64>()
+                .map_err(|_| format!("Invalid table version: {value}"))
+        })
+        .transpose()?;
+    match kind {
+        FileKind::Delta => {
+            let snapshot = delta::load_delta(path, version)?;
+            Ok((
+                snapshot.frame,
+                snapshot.history,
+                Some(snapshot.version.to_string()),
+            ))
+        }
+        FileKind::Iceberg => {
+            let snapshot = iceberg::load_iceberg(path, version)?;
+            Ok((
+                snapshot.frame,
+                snapshot.history,
+                snapshot.snapshot_id.map(|id| id.to_string()),
+            ))
+        }
+        _ => Err("Current source is not a versioned table.".to_string()),
+    }
+}
@@ -0,0 +1,148 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use polars::io::cloud::CloudOptions;
+use polars::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{FileKind, FileSpec};
+
+/// Connection settings for S3-compatible storage. Anything left unset falls
+/// back to the standard `AWS_*` environment variables.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct S3Settings {
+    pub endpoint: Option<String>,
+    pub region: Option<String>,
+    pub profile: Option<String>,
+    #[serde(default)]
+    pub allow_http: bool,
+}
+
+pub fn is_s3_uri(path: &str) -> bool {
+    path.starts_with("s3://") || path.starts_with("s3a://")
+}
+
+fn aws_dir() -> Option<PathBuf> {
+    std::env::var_os("HOME")
+        .or_else(|| std::env::var_os("USERPROFILE"))
+        .map(|home| PathBuf::from(home).join(".aws"))
+}
+
+/// Reads one section of an AWS INI file (`~/.aws/credentials` or
+/// `~/.aws/config`).
+fn ini_section(path: &Path, section: &str) -> HashMap<String, String> {
+    let Ok(text) = std::fs::read_to_string(path) else {
+        return HashMap::new();
+    };
+    let mut values = HashMap::new();
+    let mut in_section = false;
+    for line in text.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+        if let Some(name) = line
+            .strip_prefix('[')
+            .and_then(|rest| rest.strip_suffix(']'))
+        {
+            in_section = name.trim() == section;
+            continue;
+        }
+        if in_section {
+            if let Some((key, value)) = line.split_once('=') {
+                values.insert(key.trim().to_string(), value.trim().to_string());
+            }
+        }
+    }
+    values
+}
+
+fn profile_config(profile: &str) -> Vec<(&'static str, String)> {
+    let Some(dir) = aws_dir() else {
+        return Vec::new();
+    };
+    let credentials = ini_section(&dir.join("credentials"), profile);
+    let config_section = if profile == "default" {
+        "default".to_string()
+    } else {
+        format!("profile {profile}")
+    };
+    let config = ini_section(&dir.join("config"), &config_section);
+
+    let lookup = |key: &str| credentials.get(key).or_else(|| config.get(key)).cloned();
+    [
+        ("aws_access_key_id", lookup("aws_access_key_id")),
+        ("aws_secret_access_key", lookup("aws_secret_access_key")),
+        ("aws_session_token", lookup("aws_session_token")),
+        ("aws_region", config.get("region").cloned()),
+        ("aws_endpoint", config.get("endpoint_url").cloned()),
+    ]
+    .into_iter()
+    .filter_map(|(key, value)| value.map(|value| (key, value)))
+    .collect()
+}
+
+/// Credentials come from the environment, or from the selected (or default)
+/// profile in `~/.aws` when no access key is set there. Explicit settings win.
+pub(crate) fn cloud_options(uri: &str, settings: &S3Settings) -> Result<CloudOptions, String> {
+    let has_env_keys = std::env::var_os("AWS_ACCESS_KEY_ID").is_some();
+    let profile = settings
+        .profile
+        .clone()
+        .or_else(|| std::env::var("AWS_PROFILE").ok());
+
+    let mut options = Vec::new();
+    if settings.profile.is_some() || !has_env_keys {
+        options.extend(profile_config(profile.as_deref().unwrap_or("default")));
+    }
+    if let Some(region) = &settings.region {
+        options.push(("aws_region", region.clone()));
+    }
+    if let Some(endpoint) = &settings.endpoint {
+        options.push(("aws_endpoint", endpoint.clone()));
+        // MinIO and most self-hosted stores only speak path-style requests.
+        options.push(("aws_virtual_hosted_style_request", "false".to_string()));
+    }
+    if settings.allow_http {
+        options.push(("aws_allow_http", "true".to_string()));
+    }
+
+    CloudOptions::from_untyped_config(uri, options).map_err(|err| err.to_string())
+}
+
+/// Builds a lazy scan over an `s3://` URI or glob. Parquet is scanned with
+/// ranged reads, so only the footer and the column chunks a query touches are
+/// fetched.
+pub(crate) fn load_s3(
+    uri: &str,
+    spec: &FileSpec,
+    settings: &S3Settings,
+) -> Result<LazyFrame, String> {
+    if spec.compressed {
+        return Err("Compressed files cannot be read from S3".to_string());
+    }
+    let options = Some(cloud_options(uri, settings)?);
+    match spec.kind {
+        FileKind::Parquet => {
+            let args = ScanArgsParquet {
+                cloud_options: options,
+                ..Default::default()
+            };
+            LazyFrame::scan_parquet(uri, args).map_err(|err| err.to_string())
+        }
+        FileKind::Arrow => {
+            let args = ScanArgsIpc {
+                cloud_options: options,
+                ..Default::default()
+            };
+            LazyFrame::scan_ipc(uri, args).map_err(|err| err.to_string())
+        }
+        FileKind::Csv => LazyCsvReader::new(uri)
+            .with_separator(if spec.extension == "tsv" { b'\t' } else { b',' })
+            .with_try_parse_dates(true)
+            .with_cloud_options(options)
+            .finish()
+            .map_err(|err| err.to_string()),
+        _ => Err("Only Parquet, CSV and Arrow/IPC files can be read from S3".to_string()),
+    }
+}
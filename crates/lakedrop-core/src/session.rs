@@ -0,0 +1,576 @@
+use std::path::{Path, PathBuf};
+
+use polars::prelude::*;
+use polars::sql::SQLContext;
+
+use crate::error::NO_FILE;
+use crate::export::{export_frame, ExportOptions};
+use crate::fixed_width::{self, FixedWidthOptions};
+use crate::s3::{self, S3Settings};
+use crate::{
+    archive, check_compression, detect_file_kind, detect_kind_from_name, excel, http,
+    lazy_row_count, load_archive_member, load_lazy_frame, load_table_version, schema_to_fields,
+    sql, sqlite, storage_info, Completion, Error, ErrorCode, FileKind, FileMetadata, FileSpec,
+    QueryResult, TableInfo,
+};
+
+/// One open source, registered as `source` for SQL, plus any extra tables
+/// and the selection (sheet, version, layout) it was loaded with.
+#[derive(Default)]
+pub struct Session {
+    source: Option<LazyFrame>,
+    /// The path or URI as the user opened it; `file_path` may point at a
+    /// downloaded copy instead.
+    source_path: Option<String>,
+    file_path: Option<PathBuf>,
+    file_kind: Option<FileKind>,
+    sheets: Vec<String>,
+    active_sheet: Option<String>,
+    active_version: Option<String>,
+    s3: S3Settings,
+    tables: Vec<(String, LazyFrame)>,
+    fixed_width: Option<FixedWidthOptions>,
+}
+
+fn frame_schema(lf: &LazyFrame) -> Result<Schema, String> {
+    Ok(lf
+        .clone()
+        .schema()
+        .map_err(|err| err.to_string())?
+        .as_ref()
+        .clone())
+}
+
+/// Metadata shared by every selection of a local file; callers fill in the
+/// kind-specific parts.
+fn describe(path: &Path, kind: FileKind, schema: &Schema, row_count: u64) -> FileMetadata {
+    FileMetadata {
+        file_name: path
+            .file_name()
+            .and_then(|value| value.to_str())
+            .unwrap_or("data")
+            .to_string(),
+        file_path: path.display().to_string(),
+        file_size: std::fs::metadata(path).map(|meta| meta.len()).unwrap_or(0),
+        file_kind: kind,
+        row_count,
+        schema: schema_to_fields(schema),
+        sheets: Vec::new(),
+        active_sheet: None,
+        ranges: Vec::new(),
+        tables: Vec::new(),
+        storage: None,
+        versions: Vec::new(),
+        active_version: None,
+        fixed_width: None,
+    }
+}
+
+impl Session {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn s3_settings(&self) -> &S3Settings {
+        &self.s3
+    }
+
+    /// Used for `s3://` sources opened after this call.
+    pub fn set_s3_settings(&mut self, settings: S3Settings) {
+        self.s3 = settings;
+    }
+
+    /// Opens a local file or directory table, an `s3://` URI or an
+    /// `http(s)://` Parquet URL, replacing the current source and dropping
+    /// registered tables. Other HTTP files have to be downloaded with
+    /// [`http::fetch_cached`] and opened with [`Session::open_downloaded`].
+    pub fn open(&mut self, source: &str) -> Result<FileMetadata, Error> {
+        if http::requires_download(source) {
+            return Err(
+                format!("{source} must be downloaded first; see Session::open_downloaded").into(),
+            );
+        }
+        self.open_at(source, None)
+    }
+
+    /// Opens the local copy of `url`, remembering `url` as the source path.
+    pub fn open_downloaded(&mut self, url: &str, local: PathBuf) -> Result<FileMetadata, Error> {
+        self.open_at(url, Some(local))
+    }
+
+    fn open_at(&mut self, source: &str, local: Option<PathBuf>) -> Result<FileMetadata, Error> {
+        let is_url = s3::is_s3_uri(source) || http::is_http_url(source);
+        let display_path = if is_url {
+            PathBuf::from(http::url_path(source))
+        } else {
+            PathBuf::from(source)
+        };
+        // Remote sources are scanned in place; everything else is read from
+        // disk, possibly from a downloaded copy.
+        let remote = is_url && local.is_none();
+        let path = local.unwrap_or_else(|| PathBuf::from(source));
+        if !remote && !path.exists() {
+            return Err(Error::file_not_found(&path));
+        }
+        let spec = if remote {
+            detect_kind_from_name(&display_path)?
+        } else {
+            detect_file_kind(&path)?
+        };
+        check_compression(&display_path, &spec)?;
+        self.read_at(source, path, &display_path, remote, &spec)
+            .map_err(|message| Error::new(ErrorCode::ReadFailed, message).with_path(source))
+    }
+
+    /// Loads a detected source and makes it current.
+    fn read_at(
+        &mut self,
+        source: &str,
+        path: PathBuf,
+        display_path: &Path,
+        remote: bool,
+        spec: &FileSpec,
+    ) -> Result<FileMetadata, String> {
+        let is_url = s3::is_s3_uri(source) || http::is_http_url(source);
+        let file_size = std::fs::metadata(&path).map(|meta| meta.len()).unwrap_or(0);
+
+        let mut versions = Vec::new();
+        let mut active_version = None;
+        let mut fixed_width = None;
+        let mut ranges = Vec::new();
+        let (lf, sheets, active_sheet, row_count, schema) = if remote {
+            let lf = if s3::is_s3_uri(source) {
+                s3::load_s3(source, spec, &self.s3)?
+            } else {
+                LazyFrame::scan_parquet(source, ScanArgsParquet::default())
+                    .map_err(|err| err.to_string())?
+            };
+            let schema = frame_schema(&lf)?;
+            let row_count = lazy_row_count(&lf).unwrap_or(0);
+            (lf, Vec::new(), None, row_count, schema)
+        } else if spec.kind == FileKind::Excel {
+            let workbook = excel::load_excel_sheet(&path, None)?;
+            let schema = workbook.frame.schema();
+            let row_count = workbook.frame.height() as u64;
+            ranges = workbook.ranges;
+            (
+                workbook.frame.lazy(),
+                workbook.sheets,
+                Some(workbook.active),
+                row_count,
+                schema,
+            )
+        } else if spec.kind == FileKind::Sqlite {
+            let (df, tables, active_table) = sqlite::load_sqlite_table(&path, None)?;
+            let schema = df.schema();
+            let row_count = df.height() as u64;
+            (df.lazy(), tables, Some(active_table), row_count, schema)
+        } else if spec.kind == FileKind::Archive {
+            let (lf, members, active_member) = load_archive_member(&path, spec, None)?;
+            let schema = frame_schema(&lf)?;
+            let row_count = lazy_row_count(&lf).unwrap_or(0);
+            (lf, members, Some(active_member), row_count, schema)
+        } else if matches!(spec.kind, FileKind::Delta | FileKind::Iceberg) {
+            let (lf, history, version) = load_table_version(&path, spec.kind, None)?;
+            let schema = frame_schema(&lf)?;
+            let row_count = lazy_row_count(&lf).unwrap_or(0);
+            versions = history;
+            active_version = version;
+            (lf, Vec::new(), None, row_count, schema)
+        } else if spec.kind == FileKind::FixedWidth {
+            let options = FixedWidthOptions::default();
+            let (df, layout) = fixed_width::load_fixed_width(&path, spec.compressed, &options)?;
+            let schema = df.schema();
+            let row_count = df.height() as u64;
+            fixed_width = Some(layout);
+            (df.lazy(), Vec::new(), None, row_count, schema)
+        } else {
+            let lf = load_lazy_frame(&path, spec).map_err(|err| err.message)?;
+            let schema = frame_schema(&lf)?;
+            let row_count = lazy_row_count(&lf).unwrap_or(0);
+            (lf, Vec::new(), None, row_count, schema)
+        };
+
+        let metadata = FileMetadata {
+            file_name: display_path
+                .file_name()
+                .and_then(|value| value.to_str())
+                .unwrap_or("data")
+                .to_string(),
+            file_path: if is_url {
+                source.to_string()
+            } else {
+                path.display().to_string()
+            },
+            file_size,
+            file_kind: spec.kind,
+            row_count,
+            schema: schema_to_fields(&schema),
+            sheets,
+            active_sheet,
+            ranges,
+            tables: Vec::new(),
+            storage: if remote {
+                None
+            } else {
+                storage_info(&path, spec)
+            },
+            versions,
+            active_version,
+            fixed_width,
+        };
+
+        self.source = Some(lf);
+        self.source_path = Some(source.to_string());
+        self.file_path = Some(path);
+        self.file_kind = Some(spec.kind);
+        self.sheets = metadata.sheets.clone();
+        self.active_sheet = metadata.active_sheet.clone();
+        self.active_version = metadata.active_version.clone();
+        self.fixed_width = metadata.fixed_width.clone();
+        self.tables.clear();
+
+        Ok(metadata)
+    }
+
+    /// The open local file (or downloaded copy) and how it was read.
+    fn current(&self) -> Result<(PathBuf, FileKind), Error> {
+        let path = self.file_path.clone().ok_or_else(Error::no_source)?;
+        let kind = self.file_kind.ok_or_else(Error::no_source)?;
+        Ok((path, kind))
+    }
+
+    pub fn select_excel_sheet(&mut self, sheet: &str) -> Result<FileMetadata, Error> {
+        let (path, kind) = self.current()?;
+        if kind != FileKind::Excel {
+            return Err("Current file is not an Excel workbook.".into());
+        }
+
+        let workbook = excel::load_excel_sheet(&path, Some(sheet.to_string()))
+            .map_err(|message| Error::read_failed(&path, message))?;
+        let schema = workbook.frame.schema();
+        let row_count = workbook.frame.height() as u64;
+        let metadata = FileMetadata {
+            sheets: workbook.sheets,
+            active_sheet: Some(workbook.active),
+            ranges: workbook.ranges,
+            tables: self.table_names(),
+            ..describe(&path, kind, &schema, row_count)
+        };
+
+        self.source = Some(workbook.frame.lazy());
+        self.sheets = metadata.sheets.clone();
+        self.active_sheet = metadata.active_sheet.clone();
+        Ok(metadata)
+    }
+
+    pub fn select_sqlite_table(&mut self, table: &str) -> Result<FileMetadata, Error> {
+        let (path, kind) = self.current()?;
+        if kind != FileKind::Sqlite {
+            return Err("Current file is not a SQLite database.".into());
+        }
+
+        let (df, tables, active_table) = sqlite::load_sqlite_table(&path, Some(table.to_string()))
+            .map_err(|message| Error::read_failed(&path, message))?;
+        let schema = df.schema();
+        let row_count = df.height() as u64;
+        let metadata = FileMetadata {
+            sheets: tables,
+            active_sheet: Some(active_table),
+            ..describe(&path, kind, &schema, row_count)
+        };
+
+        self.source = Some(df.lazy());
+        self.sheets = metadata.sheets.clone();
+        self.active_sheet = metadata.active_sheet.clone();
+        Ok(metadata)
+    }
+
+    /// Switches a Delta or Iceberg table to another version or snapshot id.
+    pub fn select_version(&mut self, version: &str) -> Result<FileMetadata, Error> {
+        let (path, kind) = self.current()?;
+
+        let (lf, history, active_version) = load_table_version(&path, kind, Some(version))
+            .map_err(|message| Error::read_failed(&path, message))?;
+        let schema = frame_schema(&lf).map_err(|message| Error::read_failed(&path, message))?;
+        let row_count = lazy_row_count(&lf).unwrap_or(0);
+        let metadata = FileMetadata {
+            versions: history,
+            active_version,
+            ..describe(&path, kind, &schema, row_count)
+        };
+
+        self.source = Some(lf);
+        self.active_version = metadata.active_version.clone();
+        Ok(metadata)
+    }
+
+    pub fn select_archive_member(&mut self, member: &str) -> Result<FileMetadata, Error> {
+        let (path, kind) = self.current()?;
+        if kind != FileKind::Archive {
+            return Err("Current file is not an archive.".into());
+        }
+
+        let spec = detect_file_kind(&path)?;
+        let (lf, members, active_member) =
+            load_archive_member(&path, &spec, Some(member.to_string()))
+                .map_err(|message| Error::read_failed(&path, message))?;
+        let schema = frame_schema(&lf).map_err(|message| Error::read_failed(&path, message))?;
+        let row_count = lazy_row_count(&lf).unwrap_or(0);
+        let metadata = FileMetadata {
+            sheets: members,
+            active_sheet: Some(active_member),
+            tables: self.table_names(),
+            ..describe(&path, kind, &schema, row_count)
+        };
+
+        self.source = Some(lf);
+        self.sheets = metadata.sheets.clone();
+        self.active_sheet = metadata.active_sheet.clone();
+        Ok(metadata)
+    }
+
+    /// Registers every data file of the open archive as its own SQL table,
+    /// named after the member's file stem. `source` keeps pointing at the
+    /// selected member.
+    pub fn register_archive_members(&mut self) -> Result<Vec<String>, Error> {
+        let (path, kind) = self.current()?;
+        if kind != FileKind::Archive {
+            return Err("Current file is not an archive.".into());
+        }
+
+        let spec = detect_file_kind(&path)?;
+        let mut tables: Vec<(String, LazyFrame)> = Vec::new();
+        let members = archive::list_members(&path, &spec.extension, spec.compressed)
+            .map_err(|message| Error::read_failed(&path, message))?;
+        for member in members {
+            let (lf, _, _) = load_archive_member(&path, &spec, Some(member.clone()))
+                .map_err(|message| Error::read_failed(&path, message))?;
+            let taken = tables
+                .iter()
+                .map(|(name, _)| name.clone())
+                .collect::<Vec<_>>();
+            tables.push((archive::table_name(&member, &taken), lf));
+        }
+
+        self.tables = tables;
+        Ok(self.table_names())
+    }
+
+    /// Registers every Excel Table and named range of the open workbook as a
+    /// SQL table under its own name. `source` keeps pointing at the selected
+    /// sheet.
+    pub fn register_excel_ranges(&mut self) -> Result<Vec<String>, Error> {
+        let (path, kind) = self.current()?;
+        if kind != FileKind::Excel {
+            return Err("Current file is not an Excel workbook.".into());
+        }
+
+        self.tables = excel::load_excel_ranges(&path)
+            .map_err(|message| Error::read_failed(&path, message))?
+            .into_iter()
+            .map(|(name, df)| {
+                let name = if name == "source" {
+                    format!("{name}_1")
+                } else {
+                    name
+                };
+                (name, df.lazy())
+            })
+            .collect();
+        Ok(self.table_names())
+    }
+
+    /// Registers `frame` as an extra SQL table, replacing one with the same
+    /// name.
+    pub fn register(&mut self, name: &str, frame: LazyFrame) {
+        self.tables.retain(|(existing, _)| existing != name);
+        self.tables.push((name.to_string(), frame));
+    }
+
+    /// Re-reads the open text file as fixed-width columns. An empty column
+    /// list infers the layout from the header or ruler line; the resolved
+    /// layout is returned so it can be edited and applied again.
+    pub fn set_fixed_width_layout(
+        &mut self,
+        options: &FixedWidthOptions,
+    ) -> Result<FileMetadata, Error> {
+        let path = self.file_path.clone().ok_or_else(Error::no_source)?;
+        let spec = detect_file_kind(&path)?;
+        if !matches!(spec.kind, FileKind::Csv | FileKind::FixedWidth) {
+            return Err("Fixed-width layout only applies to text files.".into());
+        }
+
+        let (df, layout) = fixed_width::load_fixed_width(&path, spec.compressed, options)
+            .map_err(|message| Error::read_failed(&path, message))?;
+        let schema = df.schema();
+        let row_count = df.height() as u64;
+        let metadata = FileMetadata {
+            fixed_width: Some(layout),
+            ..describe(&path, FileKind::FixedWidth, &schema, row_count)
+        };
+
+        self.source = Some(df.lazy());
+        self.file_kind = Some(FileKind::FixedWidth);
+        self.fixed_width = metadata.fixed_width.clone();
+        Ok(metadata)
+    }
+
+    /// Rebuilds `source` from the file on disk after it changed, keeping the
+    /// fixed-width layout, and returns the new row count. Meant for flat
+    /// files; other kinds fall back to their default selection.
+    pub fn reload(&mut self) -> Result<u64, Error> {
+        let (path, kind) = self.current()?;
+        let mut spec = detect_file_kind(&path)?;
+        // `set_fixed_width_layout` may have reinterpreted a .txt file.
+        spec.kind = kind;
+
+        let lf = match (kind, &self.fixed_width) {
+            (FileKind::FixedWidth, Some(options)) => {
+                fixed_width::load_fixed_width(&path, spec.compressed, options)
+                    .map_err(|message| Error::read_failed(&path, message))?
+                    .0
+                    .lazy()
+            }
+            _ => load_lazy_frame(&path, &spec)?,
+        };
+        let row_count =
+            lazy_row_count(&lf).map_err(|message| Error::read_failed(&path, message))?;
+        self.source = Some(lf);
+        Ok(row_count)
+    }
+
+    /// SQL context with the current source as `source` plus any extra tables.
+    pub fn context(&self) -> Result<SQLContext, Error> {
+        let source = self.source.as_ref().ok_or_else(Error::no_source)?;
+        let mut ctx = SQLContext::new();
+        ctx.register("source", source.clone());
+        for (name, lf) in &self.tables {
+            ctx.register(name, lf.clone());
+        }
+        Ok(ctx)
+    }
+
+    /// Plans `sql` against the current tables without running it.
+    pub fn query(&self, sql: &str) -> Result<LazyFrame, Error> {
+        let mut ctx = self.context()?;
+        sql::parse(sql)?;
+        ctx.execute(sql).map_err(|err| self.explain(err, sql))
+    }
+
+    /// Plans and runs `sql`.
+    pub fn collect(&self, sql: &str) -> Result<DataFrame, Error> {
+        self.query(sql)?
+            .collect()
+            .map_err(|err| self.explain(err, sql))
+    }
+
+    /// Completions for the word at `cursor`, a UTF-16 offset into `sql`,
+    /// from the open tables and the Polars SQL functions and keywords.
+    pub fn complete(&self, sql: &str, cursor: usize) -> Vec<Completion> {
+        let tables = self.tables().unwrap_or_default();
+        sql::complete(sql, cursor, &tables)
+    }
+
+    /// Turns a failure of planning or running `sql` into an [`Error`] that
+    /// points into `sql`, with "did you mean" names from the open tables.
+    pub fn explain(&self, err: PolarsError, sql: &str) -> Error {
+        let error = Error::from_query(err, sql);
+        match self.tables() {
+            Ok(tables) => sql::suggest_identifiers(error, sql, &tables),
+            Err(_) => error,
+        }
+    }
+
+    /// Runs `sql` and returns at most `max_rows` rows as JSON values, plus
+    /// the full row count.
+    pub fn execute(&self, sql: &str, max_rows: Option<usize>) -> Result<QueryResult, Error> {
+        let df = self.collect(sql)?;
+        QueryResult::from_frame(df, max_rows).map_err(Error::from)
+    }
+
+    /// Runs `sql` and writes the full result to `path`.
+    pub fn export(&self, sql: &str, path: &Path, options: &ExportOptions) -> Result<(), Error> {
+        let df = self.collect(sql)?;
+        export_frame(df, path, options)
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.source.is_some()
+    }
+
+    /// Schema of the current source.
+    pub fn schema(&self) -> Result<Schema, String> {
+        frame_schema(self.source.as_ref().ok_or(NO_FILE)?)
+    }
+
+    /// Row count of the current source.
+    pub fn row_count(&self) -> Result<u64, String> {
+        lazy_row_count(self.source.as_ref().ok_or(NO_FILE)?)
+    }
+
+    pub fn source_path(&self) -> Option<&str> {
+        self.source_path.as_deref()
+    }
+
+    pub fn file_path(&self) -> Option<&Path> {
+        self.file_path.as_deref()
+    }
+
+    pub fn file_kind(&self) -> Option<FileKind> {
+        self.file_kind
+    }
+
+    pub fn sheets(&self) -> &[String] {
+        &self.sheets
+    }
+
+    pub fn active_sheet(&self) -> Option<&str> {
+        self.active_sheet.as_deref()
+    }
+
+    pub fn active_version(&self) -> Option<&str> {
+        self.active_version.as_deref()
+    }
+
+    pub fn fixed_width(&self) -> Option<&FixedWidthOptions> {
+        self.fixed_width.as_ref()
+    }
+
+    /// `source` followed by the extra SQL tables, each with its schema.
+    pub fn tables(&self) -> Result<Vec<TableInfo>, String> {
+        let source = self.source.as_ref().ok_or(NO_FILE)?;
+        std::iter::once(("source", source))
+            .chain(self.tables.iter().map(|(name, lf)| (name.as_str(), lf)))
+            .map(|(name, lf)| {
+                Ok(TableInfo {
+                    name: name.to_string(),
+                    schema: schema_to_fields(&frame_schema(lf)?),
+                })
+            })
+            .collect()
+    }
+
+    /// Names of the extra SQL tables, in registration order.
+    pub fn table_names(&self) -> Vec<String> {
+        self.tables.iter().map(|(name, _)| name.clone()).collect()
+    }
+
+    /// What `sql` reads, for the query history: the open file, then
+    /// `<file>#<table>` for each extra table it names, such as an archive
+    /// member or an Excel range of that file.
+    pub fn query_sources(&self, sql: &str) -> Vec<String> {
+        let Some(path) = self.file_path() else {
+            return Vec::new();
+        };
+        let path = path.display().to_string();
+        let referenced = sql::referenced_tables(sql);
+        let tables = self
+            .tables
+            .iter()
+            .map(|(name, _)| name)
+            .filter(|name| referenced.contains(name))
+            .map(|name| format!("{path}#{name}"));
+        std::iter::once(path.clone()).chain(tables).collect()
+    }
+}
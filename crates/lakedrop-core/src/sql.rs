@@ -0,0 +1,561 @@
+//! SQL text helpers for the editor: parsing with the dialect `SQLContext`
+//! uses, finding identifiers in the text, suggesting known names and
+//! completing the word at the cursor.
+
+use std::collections::HashSet;
+
+use polars::sql::keywords::{all_functions, all_keywords};
+use serde::Serialize;
+use sqlparser::ast::Statement;
+use sqlparser::dialect::GenericDialect;
+use sqlparser::keywords::Keyword;
+use sqlparser::parser::{Parser, ParserOptions};
+use sqlparser::tokenizer::{Token, TokenWithLocation, Tokenizer};
+
+use crate::{Error, ErrorCode, SqlSpan, TableInfo};
+
+/// Suggestions offered for one unknown name.
+const MAX_SUGGESTIONS: usize = 3;
+/// Completions returned for one cursor position.
+const MAX_COMPLETIONS: usize = 200;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CompletionKind {
+    Table,
+    Column,
+    Function,
+    Keyword,
+}
+
+/// One completion for the word at the cursor, best first in the list.
+#[derive(Clone, Debug, Serialize)]
+pub struct Completion {
+    pub label: String,
+    pub kind: CompletionKind,
+    /// The dtype of a column.
+    pub detail: Option<String>,
+    /// `label`, quoted when it is not a plain identifier.
+    pub insert_text: String,
+}
+
+/// Parses `sql` the way `SQLContext::execute` does, so syntax errors come
+/// back with a position before anything is planned.
+pub(crate) fn parse(sql: &str) -> Result<Vec<Statement>, Error> {
+    Parser::new(&GenericDialect)
+        .with_options(ParserOptions {
+            trailing_commas: true,
+            ..Default::default()
+        })
+        .try_with_sql(sql)
+        .and_then(|mut parser| parser.parse_statements())
+        .map_err(|err| Error::syntax(sql, err.to_string()))
+}
+
+/// Tokens without whitespace and comments; empty if `sql` does not tokenize.
+fn tokens(sql: &str) -> Vec<TokenWithLocation> {
+    Tokenizer::new(&GenericDialect, sql)
+        .tokenize_with_location()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|token| !matches!(token.token, Token::Whitespace(_)))
+        .collect()
+}
+
+fn word_span(sql: &str, token: &TokenWithLocation) -> Option<SqlSpan> {
+    let Token::Word(word) = &token.token else {
+        return None;
+    };
+    let quotes = if word.quote_style.is_some() { 2 } else { 0 };
+    Some(SqlSpan::at(
+        sql,
+        token.location.line as usize,
+        token.location.column as usize,
+        word.value.chars().count() + quotes,
+    ))
+}
+
+fn word_value(token: &TokenWithLocation) -> Option<&str> {
+    match &token.token {
+        Token::Word(word) => Some(&word.value),
+        _ => None,
+    }
+}
+
+fn keyword(token: &TokenWithLocation) -> Option<Keyword> {
+    match &token.token {
+        Token::Word(word) => Some(word.keyword),
+        _ => None,
+    }
+}
+
+/// Where `name` first appears as an identifier, preferring an exact match
+/// over one that only differs in case.
+pub(crate) fn locate(sql: &str, name: &str) -> Option<SqlSpan> {
+    let tokens = tokens(sql);
+    let found = tokens
+        .iter()
+        .find(|token| word_value(token) == Some(name))
+        .or_else(|| {
+            tokens.iter().find(|token| {
+                word_value(token).is_some_and(|value| value.eq_ignore_ascii_case(name))
+            })
+        })?;
+    word_span(sql, found)
+}
+
+fn is_table_keyword(keyword: Keyword) -> bool {
+    matches!(keyword, Keyword::FROM | Keyword::JOIN)
+}
+
+/// Names right after FROM or JOIN.
+pub(crate) fn referenced_tables(sql: &str) -> Vec<String> {
+    table_aliases(&tokens(sql))
+        .into_iter()
+        .map(|(table, _)| table)
+        .collect()
+}
+
+/// Tables after FROM or JOIN with their alias, as in `FROM orders [AS] o`.
+fn table_aliases(tokens: &[TokenWithLocation]) -> Vec<(String, Option<String>)> {
+    let mut tables = Vec::new();
+    for (index, token) in tokens.iter().enumerate() {
+        if !keyword(token).is_some_and(is_table_keyword) {
+            continue;
+        }
+        let Some(table) = tokens.get(index + 1).and_then(word_value) else {
+            continue;
+        };
+        let mut next = index + 2;
+        if tokens.get(next).and_then(keyword) == Some(Keyword::AS) {
+            next += 1;
+        }
+        let alias = tokens
+            .get(next)
+            .filter(|token| keyword(token) == Some(Keyword::NoKeyword))
+            .and_then(word_value);
+        tables.push((table.to_string(), alias.map(str::to_string)));
+    }
+    tables
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = if ca == *cb {
+                diagonal
+            } else {
+                1 + diagonal.min(above).min(row[j])
+            };
+            diagonal = above;
+        }
+    }
+    row[b.len()]
+}
+
+/// Known names within a few edits of `name`, ignoring case, closest first.
+pub(crate) fn suggest<'a>(name: &str, known: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+    let lower = name.to_lowercase();
+    let limit = (name.chars().count() / 3).max(1);
+    let mut close: Vec<(usize, &str)> = known
+        .into_iter()
+        .filter(|candidate| *candidate != name)
+        .map(|candidate| (edit_distance(&lower, &candidate.to_lowercase()), candidate))
+        .filter(|(distance, _)| *distance <= limit)
+        .collect();
+    close.sort_unstable();
+    close.dedup();
+    close
+        .into_iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, candidate)| candidate.to_string())
+        .collect()
+}
+
+/// Adds suggestions to unknown table and column errors. Columns are looked
+/// up in the tables the query reads from, or in all of them when none of
+/// those is known.
+pub(crate) fn suggest_identifiers(err: Error, sql: &str, tables: &[TableInfo]) -> Error {
+    let Some(name) = err.identifier.clone() else {
+        return err;
+    };
+    let suggestions = match err.code {
+        ErrorCode::UnknownTable => suggest(&name, tables.iter().map(|table| table.name.as_str())),
+        ErrorCode::UnknownColumn => {
+            let referenced = referenced_tables(sql);
+            let mut scope: Vec<&TableInfo> = tables
+                .iter()
+                .filter(|table| referenced.contains(&table.name))
+                .collect();
+            if scope.is_empty() {
+                scope = tables.iter().collect();
+            }
+            suggest(
+                &name,
+                scope
+                    .iter()
+                    .flat_map(|table| table.schema.iter().map(|field| field.name.as_str())),
+            )
+        }
+        _ => return err,
+    };
+    err.with_suggestions(suggestions)
+}
+
+/// What may come at the cursor, judged from the token before the word
+/// being typed.
+enum Position {
+    /// Nothing before it: a statement starts.
+    Start,
+    /// After FROM, JOIN and the like.
+    Table,
+    /// After `name.`: the columns of the table called or aliased `name`.
+    Qualified(String),
+    /// Where an expression starts, such as after SELECT, WHERE or `(`.
+    Expression,
+    /// After a complete expression, where a keyword is likely next.
+    Clause,
+}
+
+fn position(before: &[TokenWithLocation], qualifier: Option<&str>) -> Position {
+    if let Some(qualifier) = qualifier {
+        let table = table_aliases(before)
+            .into_iter()
+            .find(|(_, alias)| alias.as_deref() == Some(qualifier))
+            .map_or_else(|| qualifier.to_string(), |(table, _)| table);
+        return Position::Qualified(table);
+    }
+    let Some(last) = before.last() else {
+        return Position::Start;
+    };
+    match &last.token {
+        Token::Word(word) => match word.keyword {
+            Keyword::FROM | Keyword::JOIN | Keyword::INTO | Keyword::TABLE | Keyword::UPDATE => {
+                Position::Table
+            }
+            Keyword::SELECT
+            | Keyword::DISTINCT
+            | Keyword::WHERE
+            | Keyword::ON
+            | Keyword::BY
+            | Keyword::HAVING
+            | Keyword::AND
+            | Keyword::OR
+            | Keyword::NOT
+            | Keyword::CASE
+            | Keyword::WHEN
+            | Keyword::THEN
+            | Keyword::ELSE
+            | Keyword::IN
+            | Keyword::USING => Position::Expression,
+            _ => Position::Clause,
+        },
+        // In `FROM a, b` a comma continues the table list.
+        Token::Comma => {
+            let clause = before.iter().rev().find_map(|token| match &token.token {
+                Token::Word(word)
+                    if matches!(
+                        word.keyword,
+                        Keyword::SELECT | Keyword::FROM | Keyword::WHERE | Keyword::BY
+                    ) =>
+                {
+                    Some(word.keyword)
+                }
+                _ => None,
+            });
+            if clause == Some(Keyword::FROM) {
+                Position::Table
+            } else {
+                Position::Expression
+            }
+        }
+        Token::LParen
+        | Token::Eq
+        | Token::DoubleEq
+        | Token::Neq
+        | Token::Lt
+        | Token::Gt
+        | Token::LtEq
+        | Token::GtEq
+        | Token::Plus
+        | Token::Minus
+        | Token::Mul
+        | Token::Div
+        | Token::Mod
+        | Token::StringConcat => Position::Expression,
+        _ => Position::Clause,
+    }
+}
+
+fn is_identifier_char(ch: char) -> bool {
+    ch.is_alphanumeric() || ch == '_'
+}
+
+/// Quotes `name` unless it can be written as a bare identifier.
+fn quote_identifier(name: &str, keywords: &[&str]) -> String {
+    let plain = name
+        .chars()
+        .next()
+        .is_some_and(|first| first.is_ascii_alphabetic() || first == '_')
+        && name
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || ch == '_')
+        && !keywords
+            .iter()
+            .any(|keyword| keyword.eq_ignore_ascii_case(name));
+    if plain {
+        name.to_string()
+    } else {
+        format!("\"{}\"", name.replace('"', "\"\""))
+    }
+}
+
+/// The byte index of `offset`, in UTF-16 code units, into `sql`. Offsets
+/// inside a surrogate pair round up; offsets past the end clamp to it.
+fn byte_index(sql: &str, offset: usize) -> usize {
+    let mut units = 0;
+    for (index, ch) in sql.char_indices() {
+        if units >= offset {
+            return index;
+        }
+        units += ch.len_utf16();
+    }
+    sql.len()
+}
+
+/// Ranks completions for the word at `cursor`, an offset into `sql` in
+/// UTF-16 code units as the editor reports it. Columns are limited to the
+/// tables the query reads from when any of them is in `tables`.
+pub(crate) fn complete(sql: &str, cursor: usize, tables: &[TableInfo]) -> Vec<Completion> {
+    let end = byte_index(sql, cursor);
+    let text = &sql[..end];
+    let start = text
+        .char_indices()
+        .rev()
+        .take_while(|(_, ch)| is_identifier_char(*ch))
+        .last()
+        .map_or(end, |(index, _)| index);
+    let qualifier = text[..start].strip_suffix('.').map(|head| {
+        let from = head
+            .char_indices()
+            .rev()
+            .take_while(|(_, ch)| is_identifier_char(*ch) || *ch == '"')
+            .last()
+            .map_or(head.len(), |(index, _)| index);
+        head[from..].trim_matches('"')
+    });
+
+    let functions = all_functions();
+    let keywords: Vec<&str> = all_keywords()
+        .into_iter()
+        .filter(|keyword| !functions.contains(keyword))
+        .collect();
+    let referenced = referenced_tables(sql);
+    let scoped = tables.iter().any(|info| referenced.contains(&info.name));
+
+    let completion = |label: &str, kind: CompletionKind, detail: Option<&str>| Completion {
+        label: label.to_string(),
+        kind,
+        detail: detail.map(str::to_string),
+        insert_text: match kind {
+            CompletionKind::Table | CompletionKind::Column => quote_identifier(label, &keywords),
+            _ => label.to_string(),
+        },
+    };
+    let columns = |table: Option<&str>| -> Vec<Completion> {
+        tables
+            .iter()
+            .filter(|info| match table {
+                Some(table) => info.name == table,
+                None => !scoped || referenced.contains(&info.name),
+            })
+            .flat_map(|info| &info.schema)
+            .map(|field| {
+                completion(
+                    &field.name,
+                    CompletionKind::Column,
+                    Some(field.dtype.as_str()),
+                )
+            })
+            .collect()
+    };
+    let table_names = || -> Vec<Completion> {
+        tables
+            .iter()
+            .map(|info| completion(&info.name, CompletionKind::Table, None))
+            .collect()
+    };
+    let function_names = || -> Vec<Completion> {
+        functions
+            .iter()
+            .map(|&name| completion(name, CompletionKind::Function, None))
+            .collect()
+    };
+    let keyword_names = || -> Vec<Completion> {
+        keywords
+            .iter()
+            .map(|&keyword| completion(keyword, CompletionKind::Keyword, None))
+            .collect()
+    };
+
+    let groups = match position(&tokens(&text[..start]), qualifier) {
+        Position::Start => vec![keyword_names()],
+        Position::Table => vec![table_names()],
+        Position::Qualified(table) => vec![columns(Some(table.as_str()))],
+        Position::Expression => vec![columns(None), function_names(), keyword_names()],
+        Position::Clause => vec![keyword_names(), columns(None), function_names()],
+    };
+
+    // Names starting with the typed text come before names containing it,
+    // each in group order.
+    let prefix = text[start..].to_lowercase();
+    let mut ranked: Vec<(bool, usize, Completion)> = groups
+        .into_iter()
+        .enumerate()
+        .flat_map(|(group, items)| items.into_iter().map(move |item| (group, item)))
+        .filter_map(|(group, item)| {
+            let label = item.label.to_lowercase();
+            if label.starts_with(&prefix) {
+                Some((false, group, item))
+            } else if label.contains(&prefix) {
+                Some((true, group, item))
+            } else {
+                None
+            }
+        })
+        .collect();
+    ranked.sort_by_key(|(contains, group, _)| (*contains, *group));
+    let mut seen = HashSet::new();
+    ranked
+        .into_iter()
+        .map(|(_, _, item)| item)
+        .filter(|item| seen.insert((item.kind, item.label.clone())))
+        .take(MAX_COMPLETIONS)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FieldInfo;
+
+    fn tables() -> Vec<TableInfo> {
+        let table = |name: &str, columns: &[(&str, &str)]| TableInfo {
+            name: name.to_string(),
+            schema: columns
+                .iter()
+                .map(|(name, dtype)| FieldInfo {
+                    name: name.to_string(),
+                    dtype: dtype.to_string(),
+                })
+                .collect(),
+        };
+        vec![
+            table("orders", &[("amount", "f64"), ("order id", "i64")]),
+            table("customers", &[("name", "str"), ("region", "str")]),
+        ]
+    }
+
+    #[test]
+    fn parse_reports_the_syntax_error_position() {
+        assert!(parse("SELECT * FROM source").is_ok());
+        let err = parse("SELECT * FORM source").unwrap_err();
+        assert_eq!(err.code, ErrorCode::SqlSyntax);
+        assert_eq!(
+            err.span,
+            Some(SqlSpan {
+                start: 9,
+                end: 13,
+                line: 1,
+                column: 10,
+            })
+        );
+    }
+
+    #[test]
+    fn locate_prefers_an_exact_match() {
+        let sql = "SELECT AMOUNT, amount FROM t";
+        assert_eq!(locate(sql, "amount").map(|span| span.start), Some(15));
+        assert_eq!(locate(sql, "Amount").map(|span| span.start), Some(7));
+        assert_eq!(locate(sql, "missing"), None);
+    }
+
+    #[test]
+    fn locate_covers_quoted_identifiers() {
+        let span = locate("SELECT\n  \"my col\" FROM t", "my col").unwrap();
+        assert_eq!((span.start, span.end), (9, 17));
+        assert_eq!((span.line, span.column), (2, 3));
+    }
+
+    #[test]
+    fn locate_counts_utf16_units() {
+        let sql = "SELECT '😀', amount FROM t";
+        let span = locate(sql, "amount").unwrap();
+        assert_eq!((span.start, span.end), (13, 19));
+        assert_eq!(span.column, 13);
+        let units: Vec<u16> = sql.encode_utf16().collect();
+        assert_eq!(
+            String::from_utf16(&units[span.start..span.end]).unwrap(),
+            "amount"
+        );
+    }
+
+    #[test]
+    fn suggest_ranks_close_names() {
+        let known = ["amount", "mount", "id", "amont"];
+        assert_eq!(suggest("amont", known), vec!["amount", "mount"]);
+        assert_eq!(suggest("ID", known), vec!["id"]);
+        assert!(suggest("region", known).is_empty());
+    }
+
+    #[test]
+    fn complete_tables_after_from() {
+        let items = complete("SELECT * FROM or", 16, &tables());
+        assert_eq!(items[0].label, "orders");
+        assert!(items.iter().all(|item| item.kind == CompletionKind::Table));
+    }
+
+    #[test]
+    fn complete_columns_of_the_queried_table() {
+        let items = complete("SELECT  FROM customers", 7, &tables());
+        let columns: Vec<_> = items
+            .iter()
+            .filter(|item| item.kind == CompletionKind::Column)
+            .map(|item| item.label.as_str())
+            .collect();
+        assert_eq!(columns, vec!["name", "region"]);
+        assert_eq!(items[0].detail.as_deref(), Some("str"));
+    }
+
+    #[test]
+    fn complete_columns_after_an_alias() {
+        let sql = "SELECT * FROM orders o WHERE o.";
+        let items = complete(sql, sql.chars().count(), &tables());
+        let labels: Vec<_> = items.iter().map(|item| item.label.as_str()).collect();
+        assert_eq!(labels, vec!["amount", "order id"]);
+        assert_eq!(items[1].insert_text, "\"order id\"");
+    }
+
+    #[test]
+    fn complete_takes_a_utf16_cursor() {
+        // The editor reports the cursor after `re` as 15: the emoji is two
+        // UTF-16 units but one character.
+        let sql = "SELECT '😀', re FROM customers";
+        let cursor = "SELECT '😀', re".encode_utf16().count();
+        assert_eq!(cursor, 15);
+        let items = complete(sql, cursor, &tables());
+        assert_eq!(items[0].label, "region");
+    }
+
+    #[test]
+    fn complete_ranks_prefix_matches_first() {
+        let items = complete("SELECT am FROM orders", 9, &tables());
+        assert_eq!(items[0].label, "amount");
+        assert_eq!(items[0].kind, CompletionKind::Column);
+    }
+}
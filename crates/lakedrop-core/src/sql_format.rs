@@ -0,0 +1,463 @@
+//! Pretty-printing SQL for the editor. Works on tokens rather than the AST so
+//! comments survive and nothing but whitespace and keyword case changes.
+
+use serde::Deserialize;
+use sqlparser::dialect::GenericDialect;
+use sqlparser::keywords::Keyword;
+use sqlparser::tokenizer::{Token, Tokenizer, Whitespace};
+
+use crate::sql::parse;
+use crate::Error;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeywordCase {
+    #[default]
+    Upper,
+    Lower,
+    Preserve,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct FormatOptions {
+    pub keyword_case: KeywordCase,
+    /// Spaces per indentation level.
+    pub indent: usize,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        Self {
+            keyword_case: KeywordCase::default(),
+            indent: 2,
+        }
+    }
+}
+
+/// Keywords whose case is changed. Other words the tokenizer knows as
+/// keywords, such as `date` or `value`, are just as often column names.
+fn is_sql_keyword(keyword: Keyword) -> bool {
+    matches!(
+        keyword,
+        Keyword::SELECT
+            | Keyword::DISTINCT
+            | Keyword::FROM
+            | Keyword::WHERE
+            | Keyword::GROUP
+            | Keyword::ORDER
+            | Keyword::BY
+            | Keyword::HAVING
+            | Keyword::LIMIT
+            | Keyword::OFFSET
+            | Keyword::JOIN
+            | Keyword::INNER
+            | Keyword::LEFT
+            | Keyword::RIGHT
+            | Keyword::FULL
+            | Keyword::OUTER
+            | Keyword::CROSS
+            | Keyword::SEMI
+            | Keyword::ANTI
+            | Keyword::ON
+            | Keyword::USING
+            | Keyword::AS
+            | Keyword::AND
+            | Keyword::OR
+            | Keyword::NOT
+            | Keyword::IN
+            | Keyword::IS
+            | Keyword::NULL
+            | Keyword::LIKE
+            | Keyword::ILIKE
+            | Keyword::BETWEEN
+            | Keyword::EXISTS
+            | Keyword::CASE
+            | Keyword::WHEN
+            | Keyword::THEN
+            | Keyword::ELSE
+            | Keyword::END
+            | Keyword::UNION
+            | Keyword::EXCEPT
+            | Keyword::INTERSECT
+            | Keyword::ALL
+            | Keyword::WITH
+            | Keyword::ASC
+            | Keyword::DESC
+            | Keyword::NULLS
+            | Keyword::TRUE
+            | Keyword::FALSE
+            | Keyword::CAST
+            | Keyword::INTERVAL
+            | Keyword::OVER
+            | Keyword::PARTITION
+            | Keyword::SHOW
+            | Keyword::TABLES
+            | Keyword::CREATE
+            | Keyword::TABLE
+            | Keyword::DROP
+            | Keyword::TRUNCATE
+            | Keyword::EXPLAIN
+    )
+}
+
+/// Words that can precede JOIN, as in `LEFT OUTER JOIN` or `LEFT SEMI JOIN`.
+fn is_join_modifier(keyword: Keyword) -> bool {
+    matches!(
+        keyword,
+        Keyword::INNER
+            | Keyword::LEFT
+            | Keyword::RIGHT
+            | Keyword::FULL
+            | Keyword::OUTER
+            | Keyword::CROSS
+            | Keyword::SEMI
+            | Keyword::ANTI
+    )
+}
+
+/// The keyword at `index` if it is one [`is_sql_keyword`] covers, written
+/// unquoted and not as part of a qualified name.
+fn sql_keyword(tokens: &[Token], index: usize) -> Option<Keyword> {
+    let Token::Word(word) = tokens.get(index)? else {
+        return None;
+    };
+    let qualified = index > 0 && tokens[index - 1] == Token::Period;
+    if word.quote_style.is_some() || qualified {
+        return None;
+    }
+    Some(word.keyword).filter(|keyword| is_sql_keyword(*keyword))
+}
+
+fn is_comment(token: &Token) -> bool {
+    matches!(
+        token,
+        Token::Whitespace(Whitespace::SingleLineComment { .. } | Whitespace::MultiLineComment(_))
+    )
+}
+
+/// Whether the token at `index` ends an operand, so a following `-` or `+`
+/// is binary.
+fn ends_operand(tokens: &[Token], index: usize) -> bool {
+    match &tokens[index] {
+        Token::Word(_) => match sql_keyword(tokens, index) {
+            Some(keyword) => matches!(
+                keyword,
+                Keyword::NULL | Keyword::TRUE | Keyword::FALSE | Keyword::END
+            ),
+            None => true,
+        },
+        Token::RParen
+        | Token::Number(..)
+        | Token::SingleQuotedString(_)
+        | Token::DoubleQuotedString(_)
+        | Token::Placeholder(_) => true,
+        _ => false,
+    }
+}
+
+/// Whether a space goes between the previous token and the one at `index`
+/// when they share a line. `prev` lists the indexes of earlier tokens that
+/// were written, most recent last.
+fn spaced(tokens: &[Token], prev: &[usize], index: usize) -> bool {
+    let Some(&last) = prev.last() else {
+        return false;
+    };
+    match (&tokens[last], &tokens[index]) {
+        (
+            _,
+            Token::Comma | Token::RParen | Token::Period | Token::SemiColon | Token::DoubleColon,
+        ) => false,
+        (Token::LParen | Token::Period | Token::DoubleColon, _) => false,
+        // A sign, as in `> -1`.
+        (Token::Minus | Token::Plus, _) => prev
+            .len()
+            .checked_sub(2)
+            .is_some_and(|before| ends_operand(tokens, prev[before])),
+        // Calls, but not `IN (..)` or `AS (..)`.
+        (Token::Word(_), Token::LParen) => sql_keyword(tokens, last).is_some_and(|keyword| {
+            !matches!(keyword, Keyword::LEFT | Keyword::RIGHT | Keyword::CAST)
+        }),
+        _ => true,
+    }
+}
+
+/// Output with the current line's indentation and what is open around the
+/// token being written.
+struct Layout<'a> {
+    options: &'a FormatOptions,
+    out: String,
+    line_start: usize,
+    line_indent: usize,
+    /// Indentation of clause keywords, one entry per open subquery.
+    bases: Vec<usize>,
+    /// Open parentheses: the indentation of the line a subquery was opened
+    /// on, or `None` for calls, lists and grouping.
+    parens: Vec<Option<usize>>,
+    /// A clause keyword was written; its content goes on the next line.
+    pending: bool,
+    /// Open CASE expressions, inside which AND and OR do not start lines.
+    cases: usize,
+    /// Inside `BETWEEN .. AND`, where AND does not start a line either.
+    between: bool,
+}
+
+impl Layout<'_> {
+    fn base(&self) -> usize {
+        self.bases.last().copied().unwrap_or(0)
+    }
+
+    /// Commas, AND/OR and clause keywords only break lines outside of calls
+    /// and lists.
+    fn at_clause_level(&self) -> bool {
+        !matches!(self.parens.last(), Some(None))
+    }
+
+    fn at_line_start(&self) -> bool {
+        self.out[self.line_start..].trim().is_empty()
+    }
+
+    /// Starts a new line at `level`, or re-indents the current one if
+    /// nothing has been written to it yet.
+    fn newline(&mut self, level: usize) {
+        self.pending = false;
+        self.line_indent = level;
+        if self.out.is_empty() {
+            return;
+        }
+        if self.at_line_start() {
+            self.out.truncate(self.line_start);
+        } else {
+            self.out.truncate(self.out.trim_end().len());
+            self.out.push('\n');
+            self.line_start = self.out.len();
+        }
+        self.out.push_str(&" ".repeat(level * self.options.indent));
+    }
+
+    /// Writes `text`, first moving to the content line of a clause keyword
+    /// that was just written.
+    fn write(&mut self, text: &str, space: bool) {
+        if self.pending {
+            self.newline(self.base() + 1);
+        } else if space && !self.at_line_start() {
+            self.out.push(' ');
+        }
+        self.out.push_str(text);
+    }
+
+    /// Writes `text` on the current line even after a clause keyword, as
+    /// for `SELECT DISTINCT`.
+    fn write_inline(&mut self, text: &str) {
+        self.out.push(' ');
+        self.out.push_str(text);
+    }
+}
+
+/// Pretty-prints `sql` with clause keywords on their own lines and their
+/// content indented below them. Fails with the position of the problem when
+/// `sql` does not parse in the dialect `SQLContext` uses.
+pub fn format_sql(sql: &str, options: &FormatOptions) -> Result<String, Error> {
+    parse(sql)?;
+    let tokens: Vec<Token> = Tokenizer::new(&GenericDialect, sql)
+        .with_unescape(false)
+        .tokenize()
+        .map_err(|err| Error::syntax(sql, err.to_string()))?
+        .into_iter()
+        .filter(|token| !matches!(token, Token::Whitespace(_)) || is_comment(token))
+        .collect();
+
+    let mut layout = Layout {
+        options,
+        out: String::new(),
+        line_start: 0,
+        line_indent: 0,
+        bases: Vec::new(),
+        parens: Vec::new(),
+        pending: false,
+        cases: 0,
+        between: false,
+    };
+    let mut written: Vec<usize> = Vec::new();
+    for (index, token) in tokens.iter().enumerate() {
+        let next_word = match tokens[index + 1..].iter().find(|token| !is_comment(token)) {
+            Some(Token::Word(word)) => Some(word.keyword),
+            _ => None,
+        };
+        let space = spaced(&tokens, &written, index);
+        let keyword = sql_keyword(&tokens, index);
+        let prev_keyword = written.last().and_then(|&prev| sql_keyword(&tokens, prev));
+        let text = match (token, keyword) {
+            (Token::Word(word), Some(_)) => match options.keyword_case {
+                KeywordCase::Upper => word.value.to_uppercase(),
+                KeywordCase::Lower => word.value.to_lowercase(),
+                KeywordCase::Preserve => word.value.clone(),
+            },
+            _ => token.to_string(),
+        };
+
+        match (token, keyword) {
+            (Token::Whitespace(Whitespace::SingleLineComment { prefix, comment }), _) => {
+                if !layout.at_line_start() {
+                    layout.out.push(' ');
+                }
+                layout.out.push_str(prefix);
+                layout.out.push_str(comment.trim_end());
+                let pending = layout.pending;
+                layout.newline(layout.line_indent);
+                layout.pending = pending;
+                continue;
+            }
+            (Token::LParen, _) => {
+                layout.write(&text, space);
+                if matches!(next_word, Some(Keyword::SELECT | Keyword::WITH)) {
+                    layout.parens.push(Some(layout.line_indent));
+                    layout.bases.push(layout.line_indent + 1);
+                } else {
+                    layout.parens.push(None);
+                }
+            }
+            (Token::RParen, _) => {
+                if let Some(Some(indent)) = layout.parens.pop() {
+                    layout.bases.pop();
+                    layout.newline(indent);
+                }
+                layout.write(&text, space);
+            }
+            (Token::Comma, _) if layout.at_clause_level() => {
+                layout.write(&text, space);
+                layout.newline(layout.base() + 1);
+            }
+            (Token::SemiColon, _) => {
+                layout.pending = false;
+                layout.write(&text, space);
+                layout.bases.clear();
+                layout.parens.clear();
+                layout.newline(0);
+                layout.out.push('\n');
+                layout.line_start = layout.out.len();
+            }
+            (_, Some(keyword)) if layout.at_clause_level() => match keyword {
+                Keyword::SELECT
+                | Keyword::FROM
+                | Keyword::WHERE
+                | Keyword::HAVING
+                | Keyword::WITH => {
+                    layout.newline(layout.base());
+                    layout.write(&text, space);
+                    layout.pending = true;
+                }
+                Keyword::GROUP | Keyword::ORDER if next_word == Some(Keyword::BY) => {
+                    layout.newline(layout.base());
+                    layout.write(&text, space);
+                }
+                Keyword::BY if matches!(prev_keyword, Some(Keyword::GROUP | Keyword::ORDER)) => {
+                    layout.write(&text, space);
+                    layout.pending = true;
+                }
+                Keyword::LIMIT
+                | Keyword::OFFSET
+                | Keyword::UNION
+                | Keyword::EXCEPT
+                | Keyword::INTERSECT => {
+                    layout.newline(layout.base());
+                    layout.write(&text, space);
+                }
+                Keyword::DISTINCT | Keyword::ALL if layout.pending => layout.write_inline(&text),
+                keyword if is_join_modifier(keyword) || keyword == Keyword::JOIN => {
+                    let after_modifier = prev_keyword.is_some_and(is_join_modifier);
+                    let joins = (index..)
+                        .map(|at| sql_keyword(&tokens, at))
+                        .find(|keyword| !keyword.is_some_and(is_join_modifier))
+                        .flatten()
+                        == Some(Keyword::JOIN);
+                    if joins && !after_modifier {
+                        layout.newline(layout.base() + 1);
+                    }
+                    layout.write(&text, space);
+                }
+                Keyword::CASE => {
+                    layout.cases += 1;
+                    layout.write(&text, space);
+                }
+                Keyword::END => {
+                    layout.cases = layout.cases.saturating_sub(1);
+                    layout.write(&text, space);
+                }
+                Keyword::BETWEEN => {
+                    layout.between = true;
+                    layout.write(&text, space);
+                }
+                Keyword::AND if layout.between => {
+                    layout.between = false;
+                    layout.write(&text, space);
+                }
+                Keyword::AND | Keyword::OR if layout.cases == 0 => {
+                    layout.newline(layout.base() + 1);
+                    layout.write(&text, space);
+                }
+                _ => layout.write(&text, space),
+            },
+            _ => layout.write(&text, space),
+        }
+        written.push(index);
+    }
+    Ok(layout.out.trim_end().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ErrorCode;
+
+    fn format(sql: &str) -> String {
+        format_sql(sql, &FormatOptions::default()).unwrap()
+    }
+
+    #[test]
+    fn puts_clauses_on_their_own_lines() {
+        let formatted = format("select a, b from t where a > 1 and b < 2 order by a limit 5");
+        assert_eq!(
+            formatted,
+            "SELECT\n  a,\n  b\nFROM\n  t\nWHERE\n  a > 1\n  AND b < 2\nORDER BY\n  a\nLIMIT 5"
+        );
+    }
+
+    #[test]
+    fn keeps_comments() {
+        let formatted = format("-- top\nselect a /* note */ from t -- end");
+        assert_eq!(
+            formatted,
+            "-- top\nSELECT\n  a /* note */\nFROM\n  t -- end"
+        );
+    }
+
+    #[test]
+    fn output_parses_and_formats_to_itself() {
+        for sql in [
+            "-- daily totals\nselect region, sum(amount) as total from orders o join customers c on o.id = c.id group by region having sum(amount) > -1 order by total desc",
+            "select * from (select a from t where a between 1 and 2) s where s.a in (1, 2)",
+            "select case when a > 0 then 'pos' else 'neg' end as sign, date from t; select 1",
+        ] {
+            let formatted = format(sql);
+            assert!(parse(&formatted).is_ok(), "{formatted}");
+            assert_eq!(format(&formatted), formatted);
+        }
+    }
+
+    #[test]
+    fn changes_only_keyword_case() {
+        let options = FormatOptions {
+            keyword_case: KeywordCase::Lower,
+            indent: 4,
+        };
+        let formatted = format_sql("SELECT Date, \"From\" FROM T", &options).unwrap();
+        assert_eq!(formatted, "select\n    Date,\n    \"From\"\nfrom\n    T");
+    }
+
+    #[test]
+    fn rejects_invalid_sql() {
+        let err = format_sql("SELECT * FORM t", &FormatOptions::default()).unwrap_err();
+        assert_eq!(err.code, ErrorCode::SqlSyntax);
+    }
+}
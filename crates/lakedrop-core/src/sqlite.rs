@@ -0,0 +1,268 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use polars::prelude::*;
+use rusqlite::types::Value;
+use rusqlite::{params_from_iter, Connection, OpenFlags};
+
+use crate::export::WriteMode;
+
+const SQLITE_MAGIC: &[u8; 16] = b"SQLite format 3\0";
+
+pub fn quote_ident(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+fn sqlite_column_type(dtype: &DataType) -> &'static str {
+    match dtype {
+        DataType::Boolean
+        | DataType::Int8
+        | DataType::Int16
+        | DataType::Int32
+        | DataType::Int64
+        | DataType::UInt8
+        | DataType::UInt16
+        | DataType::UInt32
+        | DataType::UInt64 => "INTEGER",
+        DataType::Float32 | DataType::Float64 => "REAL",
+        DataType::Binary => "BLOB",
+        _ => "TEXT",
+    }
+}
+
+fn any_value_to_sqlite(value: AnyValue) -> Value {
+    match value {
+        AnyValue::Null => Value::Null,
+        AnyValue::Boolean(value) => Value::Integer(value as i64),
+        AnyValue::Int8(value) => Value::Integer(value as i64),
+        AnyValue::Int16(value) => Value::Integer(value as i64),
+        AnyValue::Int32(value) => Value::Integer(value as i64),
+        AnyValue::Int64(value) => Value::Integer(value),
+        AnyValue::UInt8(value) => Value::Integer(value as i64),
+        AnyValue::UInt16(value) => Value::Integer(value as i64),
+        AnyValue::UInt32(value) => Value::Integer(value as i64),
+        AnyValue::UInt64(value) => i64::try_from(value)
+            .map(Value::Integer)
+            .unwrap_or_else(|_| Value::Text(value.to_string())),
+        AnyValue::Float32(value) => Value::Real(value as f64),
+        AnyValue::Float64(value) => Value::Real(value),
+        AnyValue::String(value) => Value::Text(value.to_string()),
+        AnyValue::StringOwned(value) => Value::Text(value.to_string()),
+        AnyValue::Binary(value) => Value::Blob(value.to_vec()),
+        AnyValue::BinaryOwned(value) => Value::Blob(value),
+        other => Value::Text(other.to_string()),
+    }
+}
+
+/// Writes `df` into `table` of the SQLite database at `path`, creating the
+/// file if needed. All rows are inserted inside a single transaction.
+pub(crate) fn write_table(
+    df: &DataFrame,
+    path: &Path,
+    table: &str,
+    mode: WriteMode,
+) -> Result<(), String> {
+    if table.trim().is_empty() {
+        return Err("SQLite table name cannot be empty".to_string());
+    }
+    if df.width() == 0 {
+        return Err("Cannot create a SQLite table from a result without columns".to_string());
+    }
+
+    // SQLite has no date/time storage class, so temporal columns go in as ISO text.
+    let columns = df
+        .get_columns()
+        .iter()
+        .map(|series| match series.dtype() {
+            DataType::Date | DataType::Datetime(_, _) | DataType::Time => series
+                .cast(&DataType::String)
+                .map_err(|err| err.to_string()),
+            _ => Ok(series.clone()),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut conn = Connection::open(path).map_err(|err| err.to_string())?;
+    let tx = conn.transaction().map_err(|err| err.to_string())?;
+
+    let table_ident = quote_ident(table);
+    if mode == WriteMode::Replace {
+        tx.execute_batch(&format!("DROP TABLE IF EXISTS {table_ident}"))
+            .map_err(|err| err.to_string())?;
+    }
+    let column_defs = df
+        .get_columns()
+        .iter()
+        .map(|series| {
+            format!(
+                "{} {}",
+                quote_ident(series.name()),
+                sqlite_column_type(series.dtype())
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+    tx.execute_batch(&format!(
+        "CREATE TABLE IF NOT EXISTS {table_ident} ({column_defs})"
+    ))
+    .map_err(|err| err.to_string())?;
+
+    {
+        let names = columns
+            .iter()
+            .map(|series| quote_ident(series.name()))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let placeholders = vec!["?"; columns.len()].join(", ");
+        let mut stmt = tx
+            .prepare(&format!(
+                "INSERT INTO {table_ident} ({names}) VALUES ({placeholders})"
+            ))
+            .map_err(|err| err.to_string())?;
+        for row_idx in 0..df.height() {
+            let mut row = Vec::with_capacity(columns.len());
+            for series in &columns {
+                let value = series.get(row_idx).map_err(|err| err.to_string())?;
+                row.push(any_value_to_sqlite(value));
+            }
+            stmt.execute(params_from_iter(row))
+                .map_err(|err| err.to_string())?;
+        }
+    }
+
+    tx.commit().map_err(|err| err.to_string())
+}
+
+pub(crate) fn sqlite_magic(path: &Path) -> Result<bool, String> {
+    let mut file = File::open(path).map_err(|err| err.to_string())?;
+    let mut buf = [0u8; 16];
+    let read = file.read(&mut buf).map_err(|err| err.to_string())?;
+    Ok(read == buf.len() && &buf == SQLITE_MAGIC)
+}
+
+fn open_read_only(path: &Path) -> Result<Connection, String> {
+    Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .map_err(|err| err.to_string())
+}
+
+fn list_tables(conn: &Connection) -> Result<Vec<String>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT name FROM sqlite_master \
+             WHERE type IN ('table', 'view') AND name NOT LIKE 'sqlite_%' \
+             ORDER BY name",
+        )
+        .map_err(|err| err.to_string())?;
+    let names = stmt
+        .query_map([], |row| row.get::<_, String>(0))
+        .map_err(|err| err.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|err| err.to_string())?;
+    Ok(names)
+}
+
+/// SQLite columns are dynamically typed, so the Polars dtype is picked from the
+/// values actually stored: integers, reals and blobs keep their type, anything
+/// mixed falls back to text.
+fn values_to_series(name: &str, values: Vec<Value>) -> Series {
+    let mut has_int = false;
+    let mut has_real = false;
+    let mut has_text = false;
+    let mut has_blob = false;
+    for value in &values {
+        match value {
+            Value::Null => {}
+            Value::Integer(_) => has_int = true,
+            Value::Real(_) => has_real = true,
+            Value::Text(_) => has_text = true,
+            Value::Blob(_) => has_blob = true,
+        }
+    }
+
+    if !has_text && !has_blob && !has_real {
+        let data = values
+            .into_iter()
+            .map(|value| match value {
+                Value::Integer(value) => Some(value),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        return Series::new(name, data);
+    }
+    if !has_text && !has_blob {
+        let data = values
+            .into_iter()
+            .map(|value| match value {
+                Value::Integer(value) => Some(value as f64),
+                Value::Real(value) => Some(value),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        return Series::new(name, data);
+    }
+    if has_blob && !has_text && !has_int && !has_real {
+        let data = values
+            .into_iter()
+            .map(|value| match value {
+                Value::Blob(value) => Some(value),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        return Series::new(name, data);
+    }
+    let data = values
+        .into_iter()
+        .map(|value| match value {
+            Value::Null => None,
+            Value::Integer(value) => Some(value.to_string()),
+            Value::Real(value) => Some(value.to_string()),
+            Value::Text(value) => Some(value),
+            Value::Blob(value) => Some(format!("{value:?}")),
+        })
+        .collect::<Vec<_>>();
+    Series::new(name, data)
+}
+
+fn read_table(conn: &Connection, table: &str) -> Result<DataFrame, String> {
+    let mut stmt = conn
+        .prepare(&format!("SELECT * FROM {}", quote_ident(table)))
+        .map_err(|err| err.to_string())?;
+    let names = stmt
+        .column_names()
+        .into_iter()
+        .map(|name| name.to_string())
+        .collect::<Vec<_>>();
+
+    let mut columns: Vec<Vec<Value>> = vec![Vec::new(); names.len()];
+    let mut rows = stmt.query([]).map_err(|err| err.to_string())?;
+    while let Some(row) = rows.next().map_err(|err| err.to_string())? {
+        for (idx, column) in columns.iter_mut().enumerate() {
+            column.push(row.get::<_, Value>(idx).map_err(|err| err.to_string())?);
+        }
+    }
+
+    let series = names
+        .iter()
+        .zip(columns)
+        .map(|(name, values)| values_to_series(name, values))
+        .collect::<Vec<_>>();
+    DataFrame::new(series).map_err(|err| err.to_string())
+}
+
+/// Loads one table of a SQLite database, defaulting to the first table by
+/// name. Returns the frame, every table name, and the table that was loaded.
+pub(crate) fn load_sqlite_table(
+    path: &Path,
+    table: Option<String>,
+) -> Result<(DataFrame, Vec<String>, String), String> {
+    let conn = open_read_only(path)?;
+    let tables = list_tables(&conn)?;
+    let active = table
+        .or_else(|| tables.first().cloned())
+        .ok_or("No tables found in database")?;
+    if !tables.contains(&active) {
+        return Err(format!("Table not found: {active}"));
+    }
+    let df = read_table(&conn, &active)?;
+    Ok((df, tables, active))
+}
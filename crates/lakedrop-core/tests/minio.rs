@@ -0,0 +1,49 @@
+//! Reads the bundled samples back from a MinIO bucket with the settings the
+//! app's S3 dialog writes. Needs a running server with the samples uploaded
+//! to the `lakedrop` bucket; `scripts/check-minio.sh` sets that up and runs
+//! this with `--ignored`.
+
+use std::path::Path;
+
+use lakedrop_core::s3::S3Settings;
+use lakedrop_core::{FileMetadata, Session};
+
+fn column_names(metadata: &FileMetadata) -> Vec<String> {
+    metadata
+        .schema
+        .iter()
+        .map(|field| field.name.clone())
+        .collect()
+}
+
+#[test]
+#[ignore = "needs a MinIO server, see scripts/check-minio.sh"]
+fn scans_samples_from_minio() {
+    let endpoint = std::env::var("LAKEDROP_MINIO_ENDPOINT").expect("LAKEDROP_MINIO_ENDPOINT");
+    let samples = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../src-tauri/resources/samples");
+
+    for name in ["sample.parquet", "sample.csv", "sample.arrow"] {
+        let mut local = Session::new();
+        let expected = local.open(&samples.join(name).to_string_lossy()).unwrap();
+
+        let mut remote = Session::new();
+        remote.set_s3_settings(S3Settings {
+            endpoint: Some(endpoint.clone()),
+            region: Some("us-east-1".to_string()),
+            profile: None,
+            allow_http: endpoint.starts_with("http://"),
+        });
+        let actual = remote.open(&format!("s3://lakedrop/{name}")).unwrap();
+
+        assert_eq!(actual.row_count, expected.row_count, "{name}");
+        assert_eq!(column_names(&actual), column_names(&expected), "{name}");
+        let counted = remote
+            .execute("SELECT COUNT(*) AS n FROM source", None)
+            .unwrap();
+        assert_eq!(
+            counted.rows[0][0],
+            serde_json::json!(expected.row_count),
+            "{name}"
+        );
+    }
+}
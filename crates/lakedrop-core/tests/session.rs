@@ -0,0 +1,286 @@
+//! Opens generated files through `Session` and checks queries, errors and
+//! exports end to end.
+
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+use lakedrop_core::{CompletionKind, ErrorCode, ExportOptions, FileKind, Session};
+use polars::prelude::*;
+
+/// A fresh directory under the system temp dir for one test.
+fn scratch(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("lakedrop-core-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn orders() -> DataFrame {
+    df!(
+        "id" => [1i64, 2, 3, 4],
+        "region" => ["north", "south", "north", "east"],
+        "amount" => [10.0, 20.5, 30.0, 5.25]
+    )
+    .unwrap()
+}
+
+fn open_orders(dir: &Path) -> Session {
+    let path = dir.join("orders.parquet");
+    ParquetWriter::new(File::create(&path).unwrap())
+        .finish(&mut orders())
+        .unwrap();
+    let mut session = Session::new();
+    session.open(&path.to_string_lossy()).unwrap();
+    session
+}
+
+fn open(path: &Path) -> Session {
+    let mut session = Session::new();
+    session.open(&path.to_string_lossy()).unwrap();
+    session
+}
+
+/// Data files below `dir`, as paths relative to it.
+fn data_files(dir: &Path) -> Vec<String> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(next) = pending.pop() {
+        for entry in std::fs::read_dir(next).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                pending.push(path);
+            } else if path.extension().is_some_and(|ext| ext == "parquet") {
+                let relative = path.strip_prefix(dir).unwrap();
+                files.push(relative.to_string_lossy().replace('\\', "/"));
+            }
+        }
+    }
+    files.sort();
+    files
+}
+
+fn manifest_rows(dir: &Path) -> u64 {
+    let manifest: serde_json::Value =
+        serde_json::from_reader(File::open(dir.join("_manifest.json")).unwrap()).unwrap();
+    manifest["total_rows"].as_u64().unwrap()
+}
+
+#[test]
+fn opens_a_file_and_describes_it() {
+    let dir = scratch("open");
+    let path = dir.join("orders.csv");
+    CsvWriter::new(File::create(&path).unwrap())
+        .finish(&mut orders())
+        .unwrap();
+
+    let mut session = Session::new();
+    let metadata = session.open(&path.to_string_lossy()).unwrap();
+    assert_eq!(metadata.file_kind, FileKind::Csv);
+    assert_eq!(metadata.row_count, 4);
+    let columns: Vec<_> = metadata.schema.iter().map(|field| &field.name).collect();
+    assert_eq!(columns, ["id", "region", "amount"]);
+    assert_eq!(session.row_count().unwrap(), 4);
+}
+
+#[test]
+fn runs_queries_against_source() {
+    let dir = scratch("query");
+    let session = open_orders(&dir);
+
+    let totals = session
+        .collect("SELECT region, SUM(amount) AS total FROM source GROUP BY region ORDER BY region")
+        .unwrap();
+    assert_eq!(totals.height(), 3);
+    let regions = totals.column("region").unwrap();
+    assert_eq!(regions.get(0).unwrap(), AnyValue::String("east"));
+    let total = totals.column("total").unwrap().get(1).unwrap();
+    assert_eq!(total, AnyValue::Float64(40.0));
+
+    let result = session
+        .execute("SELECT * FROM source ORDER BY id", Some(2))
+        .unwrap();
+    assert_eq!(result.row_count, 2);
+    assert_eq!(result.total_rows, 4);
+    assert_eq!(result.rows[1][0], serde_json::json!(2));
+}
+
+#[test]
+fn explains_query_errors() {
+    let dir = scratch("errors");
+    let session = open_orders(&dir);
+
+    let err = session.collect("SELECT * FORM source").unwrap_err();
+    assert_eq!(err.code, ErrorCode::SqlSyntax);
+    assert!(err.span.is_some());
+
+    let err = session.collect("SELECT amont FROM source").unwrap_err();
+    assert_eq!(err.code, ErrorCode::UnknownColumn);
+    assert_eq!(err.identifier.as_deref(), Some("amont"));
+    assert_eq!(err.suggestions, ["amount"]);
+    assert_eq!(err.span.map(|span| span.start), Some(7));
+
+    let err = session.collect("SELECT * FROM sorce").unwrap_err();
+    assert_eq!(err.code, ErrorCode::UnknownTable);
+    assert_eq!(err.suggestions, ["source"]);
+}
+
+#[test]
+fn selection_errors_carry_a_code() {
+    let err = Session::new().select_excel_sheet("Sheet1").err().unwrap();
+    assert_eq!(err.code, ErrorCode::NoSource);
+
+    let dir = scratch("select");
+    let mut session = open_orders(&dir);
+    let err = session.select_sqlite_table("orders").err().unwrap();
+    assert_eq!(err.code, ErrorCode::Other);
+    assert_eq!(err.message, "Current file is not a SQLite database.");
+}
+
+#[test]
+fn completes_columns_of_the_open_file() {
+    let dir = scratch("complete");
+    let session = open_orders(&dir);
+
+    let items = session.complete("SELECT re FROM source", 9);
+    assert_eq!(items[0].label, "region");
+    assert_eq!(items[0].kind, CompletionKind::Column);
+}
+
+#[test]
+fn lists_the_sources_a_query_reads() {
+    let dir = scratch("sources");
+    let mut session = open_orders(&dir);
+    session.register("regions", orders().lazy());
+    session.register("unused", orders().lazy());
+    let file = dir.join("orders.parquet").display().to_string();
+
+    assert_eq!(
+        session.query_sources("SELECT * FROM source JOIN regions USING (id)"),
+        [file.clone(), format!("{file}#regions")]
+    );
+    assert_eq!(session.query_sources("SELECT 1"), [file]);
+}
+
+#[test]
+fn exports_query_results() {
+    let dir = scratch("export");
+    let session = open_orders(&dir);
+    let sql = "SELECT * FROM source WHERE region = 'north'";
+
+    for format in ["csv", "parquet"] {
+        let path = dir.join(format!("north.{format}"));
+        let options = ExportOptions {
+            format: format.to_string(),
+            ..Default::default()
+        };
+        session.export(sql, &path, &options).unwrap();
+        let exported = open(&path);
+        assert_eq!(exported.row_count().unwrap(), 2, "{format}");
+    }
+
+    let err = session
+        .export(
+            sql,
+            &dir.join("north.txt"),
+            &ExportOptions {
+                format: "txt".to_string(),
+                ..Default::default()
+            },
+        )
+        .unwrap_err();
+    assert_eq!(err.code, ErrorCode::UnsupportedExport);
+}
+
+#[test]
+fn exports_to_sqlite_tables() {
+    let dir = scratch("sqlite");
+    let session = open_orders(&dir);
+    let path = dir.join("orders.db");
+    let options = |if_exists: &str| ExportOptions {
+        format: "sqlite".to_string(),
+        table_name: Some("orders".to_string()),
+        if_exists: Some(if_exists.to_string()),
+        ..Default::default()
+    };
+
+    session
+        .export("SELECT * FROM source", &path, &options("replace"))
+        .unwrap();
+    session
+        .export("SELECT * FROM source", &path, &options("append"))
+        .unwrap();
+    let mut exported = Session::new();
+    let metadata = exported.open(&path.to_string_lossy()).unwrap();
+    assert_eq!(metadata.file_kind, FileKind::Sqlite);
+    assert_eq!(metadata.row_count, 8);
+
+    session
+        .export("SELECT * FROM source", &path, &options("replace"))
+        .unwrap();
+    assert_eq!(open(&path).row_count().unwrap(), 4);
+}
+
+#[test]
+fn replaces_or_appends_partitioned_exports() {
+    let dir = scratch("partitioned");
+    let session = open_orders(&dir);
+    let out = dir.join("by_region");
+    let options = |if_exists: &str| ExportOptions {
+        format: "parquet".to_string(),
+        partition_by: vec!["region".to_string()],
+        write_manifest: true,
+        if_exists: Some(if_exists.to_string()),
+        ..Default::default()
+    };
+    let sql = "SELECT * FROM source";
+
+    session.export(sql, &out, &options("replace")).unwrap();
+    assert_eq!(
+        data_files(&out),
+        [
+            "region=east/part-0.parquet",
+            "region=north/part-0.parquet",
+            "region=south/part-0.parquet",
+        ]
+    );
+    assert_eq!(manifest_rows(&out), 4);
+
+    // Re-exporting does not leave the earlier files behind.
+    session.export(sql, &out, &options("replace")).unwrap();
+    assert_eq!(data_files(&out).len(), 3);
+    assert_eq!(manifest_rows(&out), 4);
+
+    session.export(sql, &out, &options("append")).unwrap();
+    assert_eq!(data_files(&out).len(), 6);
+    assert!(data_files(&out).contains(&"region=north/part-1.parquet".to_string()));
+    assert_eq!(manifest_rows(&out), 8);
+}
+
+#[test]
+fn replaces_only_what_the_manifest_lists() {
+    let dir = scratch("partitioned-replace");
+    let session = open_orders(&dir);
+    let out = dir.join("by_region");
+    let options = ExportOptions {
+        format: "parquet".to_string(),
+        partition_by: vec!["region".to_string()],
+        write_manifest: true,
+        if_exists: Some("replace".to_string()),
+        ..Default::default()
+    };
+    let sql = "SELECT * FROM source";
+    let south = "SELECT * FROM source WHERE region = 'south'";
+
+    session.export(sql, &out, &options).unwrap();
+    std::fs::write(out.join("region=north").join("notes.txt"), "keep me").unwrap();
+    session.export(south, &out, &options).unwrap();
+    assert_eq!(data_files(&out), ["region=south/part-0.parquet"]);
+    assert!(out.join("region=north").join("notes.txt").exists());
+    assert!(!out.join("region=east").exists());
+
+    // Without a manifest there is no telling which files are ours.
+    std::fs::remove_file(out.join("_manifest.json")).unwrap();
+    let err = session.export(sql, &out, &options).unwrap_err();
+    assert_eq!(err.code, ErrorCode::ExportFailed);
+    assert_eq!(data_files(&out), ["region=south/part-0.parquet"]);
+}
@@ -0,0 +1,58 @@
+#!/usr/bin/env bash
+# Checks S3 support against a throwaway MinIO container: uploads the bundled
+# samples, reads them back through the S3 settings (the ignored `minio`
+# test in lakedrop-core) and through the AWS_* environment (lakedrop-cli).
+#
+# Needs Docker and cargo. Usage: scripts/check-minio.sh
+set -euo pipefail
+
+root="$(cd "$(dirname "$0")/.." && pwd)"
+samples="$root/src-tauri/resources/samples"
+port="${LAKEDROP_MINIO_PORT:-9000}"
+endpoint="http://127.0.0.1:$port"
+network=lakedrop-minio-check
+user=lakedrop
+password=lakedrop-secret
+
+cleanup() {
+  docker rm -f lakedrop-minio >/dev/null 2>&1 || true
+  docker network rm "$network" >/dev/null 2>&1 || true
+}
+trap cleanup EXIT
+cleanup
+
+docker network create "$network" >/dev/null
+docker run -d --name lakedrop-minio --network "$network" -p "$port:9000" \
+  -e MINIO_ROOT_USER="$user" -e MINIO_ROOT_PASSWORD="$password" \
+  minio/minio server /data >/dev/null
+
+for _ in $(seq 1 30); do
+  curl -sf "$endpoint/minio/health/live" >/dev/null && break
+  sleep 1
+done
+
+docker run --rm --network "$network" -v "$samples:/samples:ro" --entrypoint sh minio/mc -c "
+  mc alias set local http://lakedrop-minio:9000 $user $password >/dev/null &&
+  mc mb local/lakedrop >/dev/null &&
+  mc cp /samples/sample.parquet /samples/sample.csv /samples/sample.arrow local/lakedrop/ >/dev/null"
+
+export AWS_ACCESS_KEY_ID="$user"
+export AWS_SECRET_ACCESS_KEY="$password"
+
+echo "S3 settings:"
+LAKEDROP_MINIO_ENDPOINT="$endpoint" cargo test --quiet \
+  --manifest-path "$root/crates/lakedrop-core/Cargo.toml" --test minio -- --ignored
+
+echo "AWS_* environment:"
+for name in sample.parquet sample.csv sample.arrow; do
+  expected="$(cargo run --quiet --manifest-path "$root/src-tauri/Cargo.toml" \
+    --bin lakedrop-cli -- count "$samples/$name")"
+  actual="$(AWS_REGION=us-east-1 AWS_ENDPOINT_URL="$endpoint" AWS_ALLOW_HTTP=true \
+    cargo run --quiet --manifest-path "$root/src-tauri/Cargo.toml" \
+    --bin lakedrop-cli -- count "s3://lakedrop/$name")"
+  if [ "$actual" != "$expected" ]; then
+    echo "$name: expected $expected rows from MinIO, got $actual" >&2
+    exit 1
+  fi
+  echo "$name: $actual rows"
+done
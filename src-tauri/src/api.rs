@@ -0,0 +1,380 @@
+//! Optional HTTP API on 127.0.0.1 so notebooks and dashboards can query
+//! whatever is open in the app. Every request needs
+//! `Authorization: Bearer <token>`.
+//!
+//! - `GET /tables`: `source` and the registered tables with their schemas
+//! - `GET /tables/<name>`: one of them
+//! - `POST /query` with `{"sql": ..., "format": ..., "max_rows": ...}`:
+//!   `json` (the default) returns the same object as `exec_sql`; `ndjson`,
+//!   `csv` and `arrow` (IPC stream) are streamed with chunked encoding.
+//!
+//! Failures are `{"error": message}`; failed queries add the other fields of
+//! [`lakedrop_core::Error`], such as `code`, `span` and `suggestions`.
+
+use std::io::{self, BufWriter, Cursor, Read, Write};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use lakedrop_core::{percent_decode, Error, QueryResult, TableInfo};
+use polars::prelude::*;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::AppState;
+
+pub(crate) const DEFAULT_PORT: u16 = 4848;
+/// External queries kept for the app's list, newest first.
+const MAX_LOGGED: usize = 200;
+const STREAM_CHUNK: usize = 64 * 1024;
+
+static NEXT_QUERY_ID: AtomicU64 = AtomicU64::new(1);
+
+/// A query an external client ran, as listed in the app.
+#[derive(Clone, Serialize)]
+pub(crate) struct ApiQuery {
+    id: u64,
+    sql: String,
+    format: String,
+    client: Option<String>,
+    started_ms: i64,
+    duration_ms: u64,
+    row_count: Option<usize>,
+    error: Option<String>,
+}
+
+#[derive(Clone, Default, Serialize)]
+pub(crate) struct ApiStatus {
+    running: bool,
+    url: Option<String>,
+    token: Option<String>,
+}
+
+/// The running server; dropping it stops accepting connections.
+pub(crate) struct ApiServer {
+    server: Arc<Server>,
+    url: String,
+    token: String,
+}
+
+impl ApiServer {
+    pub(crate) fn status(&self) -> ApiStatus {
+        ApiStatus {
+            running: true,
+            url: Some(self.url.clone()),
+            token: Some(self.token.clone()),
+        }
+    }
+}
+
+impl Drop for ApiServer {
+    fn drop(&mut self) {
+        self.server.unblock();
+    }
+}
+
+#[derive(Deserialize)]
+struct QueryRequest {
+    sql: String,
+    #[serde(default)]
+    format: Option<String>,
+    #[serde(default)]
+    max_rows: Option<usize>,
+}
+
+#[derive(Clone, Copy)]
+enum Format {
+    Json,
+    NdJson,
+    Csv,
+    Arrow,
+}
+
+impl Format {
+    fn parse(value: Option<&str>) -> Result<Self, String> {
+        match value.unwrap_or("json") {
+            "json" => Ok(Format::Json),
+            "ndjson" => Ok(Format::NdJson),
+            "csv" => Ok(Format::Csv),
+            "arrow" => Ok(Format::Arrow),
+            other => Err(format!("Unsupported format: {other}")),
+        }
+    }
+
+    fn content_type(self) -> &'static str {
+        match self {
+            Format::Json => "application/json",
+            Format::NdJson => "application/x-ndjson",
+            Format::Csv => "text/csv; charset=utf-8",
+            Format::Arrow => "application/vnd.apache.arrow.stream",
+        }
+    }
+}
+
+/// 128 bits from the OS random number generator, hex encoded.
+fn generate_token() -> Result<String, String> {
+    let mut bytes = [0u8; 16];
+    getrandom::getrandom(&mut bytes).map_err(|err| format!("Could not generate a token: {err}"))?;
+    Ok(bytes.iter().map(|byte| format!("{byte:02x}")).collect())
+}
+
+fn now_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as i64)
+        .unwrap_or(0)
+}
+
+/// Binds to 127.0.0.1 only and serves each request on its own thread.
+pub(crate) fn start(app: AppHandle, port: u16, token: Option<String>) -> Result<ApiServer, String> {
+    let token = match token.filter(|token| !token.is_empty()) {
+        Some(token) => token,
+        None => generate_token()?,
+    };
+    let server = Arc::new(Server::http(("127.0.0.1", port)).map_err(|err| err.to_string())?);
+    let addr = server
+        .server_addr()
+        .to_ip()
+        .ok_or("API server is not listening on TCP")?;
+
+    let listener = server.clone();
+    let expected = token.clone();
+    thread::spawn(move || {
+        for request in listener.incoming_requests() {
+            let app = app.clone();
+            let expected = expected.clone();
+            thread::spawn(move || handle(&app, &expected, request));
+        }
+    });
+
+    Ok(ApiServer {
+        server,
+        url: format!("http://{addr}"),
+        token,
+    })
+}
+
+/// Compares in constant time so the token cannot be guessed byte by byte.
+fn authorized(request: &Request, token: &str) -> bool {
+    request
+        .headers()
+        .iter()
+        .find(|header| header.field.equiv("Authorization"))
+        .and_then(|header| header.value.as_str().strip_prefix("Bearer "))
+        .is_some_and(|given| {
+            given.len() == token.len()
+                && given
+                    .bytes()
+                    .zip(token.bytes())
+                    .fold(0, |diff, (a, b)| diff | (a ^ b))
+                    == 0
+        })
+}
+
+fn content_type(value: &str) -> Header {
+    Header::from_bytes(&b"Content-Type"[..], value.as_bytes()).expect("valid header")
+}
+
+fn respond_json(request: Request, status: u16, body: &impl Serialize) {
+    let body = serde_json::to_vec(body).unwrap_or_default();
+    let _ = request.respond(
+        Response::from_data(body)
+            .with_status_code(status)
+            .with_header(content_type("application/json")),
+    );
+}
+
+fn respond_error(request: Request, status: u16, message: &str) {
+    respond_json(request, status, &serde_json::json!({ "error": message }));
+}
+
+fn respond_query_error(request: Request, status: u16, err: &Error) {
+    respond_json(
+        request,
+        status,
+        &serde_json::json!({
+            "error": err.message,
+            "code": err.code,
+            "span": err.span,
+            "hint": err.hint,
+            "identifier": err.identifier,
+            "suggestions": err.suggestions,
+        }),
+    );
+}
+
+fn handle(app: &AppHandle, token: &str, request: Request) {
+    if !authorized(&request, token) {
+        respond_error(request, 401, "Missing or invalid token");
+        return;
+    }
+    let method = request.method().clone();
+    let url = request.url().to_string();
+    let path = url.split(['?', '#']).next().unwrap_or_default();
+    match (method, path) {
+        (Method::Get, "/tables") => match tables(app) {
+            Ok(tables) => respond_json(request, 200, &tables),
+            Err(err) => respond_error(request, 409, &err),
+        },
+        (Method::Get, path) if path.starts_with("/tables/") => {
+            let name = percent_decode(&path["/tables/".len()..]);
+            match tables(app) {
+                Ok(tables) => match tables.into_iter().find(|table| table.name == name) {
+                    Some(table) => respond_json(request, 200, &table),
+                    None => respond_error(request, 404, &format!("Unknown table: {name}")),
+                },
+                Err(err) => respond_error(request, 409, &err),
+            }
+        }
+        (Method::Post, "/query") => query(app, request),
+        _ => respond_error(request, 404, "Not found"),
+    }
+}
+
+fn tables(app: &AppHandle) -> Result<Vec<TableInfo>, String> {
+    let state = app.state::<Mutex<AppState>>();
+    let guard = state.lock().map_err(|_| "State lock failed")?;
+    guard.session.tables()
+}
+
+/// Runs the query like `exec_sql`, but only plans it under the state lock so
+/// a slow client does not block the app.
+fn query(app: &AppHandle, mut request: Request) {
+    let client = request.remote_addr().map(|addr| addr.to_string());
+    let body = match serde_json::from_reader::<_, QueryRequest>(request.as_reader()) {
+        Ok(body) => body,
+        Err(err) => return respond_error(request, 400, &format!("Invalid request: {err}")),
+    };
+    let format = match Format::parse(body.format.as_deref()) {
+        Ok(format) => format,
+        Err(err) => return respond_error(request, 400, &err),
+    };
+
+    let started_ms = now_ms();
+    let started = Instant::now();
+    let planned = app
+        .state::<Mutex<AppState>>()
+        .lock()
+        .map_err(|_| Error::from("State lock failed"))
+        .and_then(|guard| guard.session.query(&body.sql));
+    let collected = planned.and_then(|lf| lf.collect().map_err(|err| explain(app, err, &body.sql)));
+
+    log_query(
+        app,
+        ApiQuery {
+            id: NEXT_QUERY_ID.fetch_add(1, Ordering::Relaxed),
+            sql: body.sql,
+            format: body.format.unwrap_or_else(|| "json".to_string()),
+            client,
+            started_ms,
+            duration_ms: started.elapsed().as_millis() as u64,
+            row_count: collected.as_ref().ok().map(DataFrame::height),
+            error: collected.as_ref().err().map(|err| err.message.clone()),
+        },
+    );
+
+    let df = match collected {
+        Ok(df) => df,
+        Err(err) => return respond_query_error(request, 400, &err),
+    };
+    match format {
+        Format::Json => match QueryResult::from_frame(df, body.max_rows) {
+            Ok(result) => respond_json(request, 200, &result),
+            Err(err) => respond_error(request, 500, &err),
+        },
+        _ => {
+            let df = match body.max_rows {
+                Some(max_rows) => df.head(Some(max_rows)),
+                None => df,
+            };
+            stream(request, df, format);
+        }
+    }
+}
+
+/// Like `Session::explain`, taking the lock again only once a query failed.
+fn explain(app: &AppHandle, err: PolarsError, sql: &str) -> Error {
+    let state = app.state::<Mutex<AppState>>();
+    match state.lock() {
+        Ok(guard) => guard.session.explain(err, sql),
+        Err(_) => Error::from_query(err, sql),
+    }
+}
+
+fn log_query(app: &AppHandle, entry: ApiQuery) {
+    let state = app.state::<Mutex<AppState>>();
+    if let Ok(mut guard) = state.lock() {
+        guard.api_queries.push_front(entry.clone());
+        guard.api_queries.truncate(MAX_LOGGED);
+    }
+    let _ = app.emit("api-query", entry);
+}
+
+/// Serializes on a separate thread and hands the bytes over in chunks, so
+/// large results start arriving before they are fully encoded.
+fn stream(request: Request, mut df: DataFrame, format: Format) {
+    let (sender, receiver) = mpsc::sync_channel(8);
+    thread::spawn(move || {
+        let mut out = BufWriter::with_capacity(STREAM_CHUNK, ChannelWriter(sender));
+        // A failure here can only truncate the body; the status is already sent.
+        let _ = match format {
+            Format::Csv => CsvWriter::new(&mut out).finish(&mut df),
+            Format::Arrow => IpcStreamWriter::new(&mut out).finish(&mut df),
+            _ => JsonWriter::new(&mut out)
+                .with_json_format(JsonFormat::JsonLines)
+                .finish(&mut df),
+        };
+        let _ = out.flush();
+    });
+
+    let reader = ChannelReader {
+        receiver,
+        chunk: Cursor::new(Vec::new()),
+    };
+    let _ = request.respond(Response::new(
+        200.into(),
+        vec![content_type(format.content_type())],
+        reader,
+        None,
+        None,
+    ));
+}
+
+struct ChannelWriter(SyncSender<Vec<u8>>);
+
+impl Write for ChannelWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0
+            .send(buf.to_vec())
+            .map_err(|_| io::Error::from(io::ErrorKind::BrokenPipe))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+struct ChannelReader {
+    receiver: Receiver<Vec<u8>>,
+    chunk: Cursor<Vec<u8>>,
+}
+
+impl Read for ChannelReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            let read = self.chunk.read(buf)?;
+            if read > 0 || buf.is_empty() {
+                return Ok(read);
+            }
+            match self.receiver.recv() {
+                Ok(next) => self.chunk = Cursor::new(next),
+                // The writer thread is done.
+                Err(_) => return Ok(0),
+            }
+        }
+    }
+}
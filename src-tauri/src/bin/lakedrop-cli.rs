@@ -0,0 +1,243 @@
+//! `lakedrop-cli`: the lakedrop-core loaders and SQL engine for scripts and
+//! CI. Errors go to stderr; stdout only carries the requested output so it
+//! can be piped.
+
+use std::io::{self, Write};
+use std::path::Path;
+
+use lakedrop_core::{export_extension, write_export, Error, FileMetadata, Session};
+use polars::prelude::*;
+
+/// The command ran and its output was written.
+const EXIT_OK: i32 = 0;
+/// The input could not be read, the query failed or the output could not
+/// be written.
+const EXIT_FAILURE: i32 = 1;
+/// Unknown subcommand, missing argument or bad flag.
+const EXIT_USAGE: i32 = 2;
+
+const USAGE: &str = "\
+Usage:
+  lakedrop-cli schema <file> [--format table|json]
+  lakedrop-cli count <file>
+  lakedrop-cli query <file> --sql <sql> [--format table|csv|json|ndjson] [--limit <n>]
+  lakedrop-cli convert <in> <out> [--sql <sql>]
+
+The file is available to SQL as `source`. `convert` picks the output format
+from the extension of <out> (csv, parquet or xlsx).
+
+Exit codes: 0 success, 1 load/query/write failure, 2 usage error.";
+
+enum Failure {
+    Usage(String),
+    Run(String),
+}
+
+impl From<String> for Failure {
+    fn from(message: String) -> Self {
+        Failure::Run(message)
+    }
+}
+
+impl From<Error> for Failure {
+    fn from(err: Error) -> Self {
+        Failure::Run(match err.hint {
+            Some(hint) => format!("{}\nhint: {hint}", err.message),
+            None => err.message,
+        })
+    }
+}
+
+#[derive(Clone, Copy)]
+enum OutputFormat {
+    Table,
+    Csv,
+    Json,
+    NdJson,
+}
+
+#[derive(Default)]
+struct Args {
+    positional: Vec<String>,
+    sql: Option<String>,
+    format: Option<String>,
+    limit: Option<u32>,
+}
+
+fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Args, Failure> {
+    let mut parsed = Args::default();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let mut value = |flag: &str| {
+            args.next()
+                .ok_or_else(|| Failure::Usage(format!("{flag} needs a value")))
+        };
+        match arg.as_str() {
+            "--sql" => parsed.sql = Some(value("--sql")?),
+            "--format" => parsed.format = Some(value("--format")?),
+            "--limit" => {
+                let limit = value("--limit")?;
+                parsed.limit = Some(
+                    limit
+                        .parse()
+                        .map_err(|_| Failure::Usage(format!("Invalid --limit: {limit}")))?,
+                );
+            }
+            flag if flag.starts_with("--") => {
+                return Err(Failure::Usage(format!("Unknown option: {flag}")))
+            }
+            _ => parsed.positional.push(arg),
+        }
+    }
+    Ok(parsed)
+}
+
+fn output_format(format: Option<&str>, allowed: &[&str]) -> Result<OutputFormat, Failure> {
+    let format = format.unwrap_or("table");
+    if !allowed.contains(&format) {
+        return Err(Failure::Usage(format!("Unsupported --format: {format}")));
+    }
+    Ok(match format {
+        "csv" => OutputFormat::Csv,
+        "json" => OutputFormat::Json,
+        "ndjson" => OutputFormat::NdJson,
+        _ => OutputFormat::Table,
+    })
+}
+
+/// Opens `path` in a fresh session, where it is available to SQL as `source`
+/// the same way the app's editor sees it.
+fn open(path: &str) -> Result<(Session, FileMetadata), Error> {
+    let mut session = Session::new();
+    let metadata = session.open(path)?;
+    Ok((session, metadata))
+}
+
+fn print_frame(mut df: DataFrame, format: OutputFormat) -> Result<(), String> {
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    match format {
+        OutputFormat::Table => {
+            // Print every row and column rather than polars' elided preview.
+            for var in ["POLARS_FMT_MAX_ROWS", "POLARS_FMT_MAX_COLS"] {
+                if std::env::var_os(var).is_none() {
+                    std::env::set_var(var, "-1");
+                }
+            }
+            writeln!(out, "{df}").map_err(|err| err.to_string())?;
+        }
+        OutputFormat::Csv => CsvWriter::new(&mut out)
+            .finish(&mut df)
+            .map_err(|err| err.to_string())?,
+        OutputFormat::Json | OutputFormat::NdJson => {
+            JsonWriter::new(&mut out)
+                .with_json_format(if matches!(format, OutputFormat::Json) {
+                    JsonFormat::Json
+                } else {
+                    JsonFormat::JsonLines
+                })
+                .finish(&mut df)
+                .map_err(|err| err.to_string())?;
+            writeln!(out).map_err(|err| err.to_string())?;
+        }
+    }
+    out.flush().map_err(|err| err.to_string())
+}
+
+fn file_arg(args: &Args, index: usize, name: &str) -> Result<String, Failure> {
+    args.positional
+        .get(index)
+        .cloned()
+        .ok_or_else(|| Failure::Usage(format!("Missing <{name}>")))
+}
+
+fn schema(args: &Args) -> Result<(), Failure> {
+    let format = output_format(args.format.as_deref(), &["table", "json"])?;
+    let (_, metadata) = open(&file_arg(args, 0, "file")?)?;
+    let fields = metadata.schema;
+    let text = match format {
+        OutputFormat::Json => {
+            serde_json::to_string_pretty(&fields).map_err(|err| err.to_string())?
+        }
+        _ => fields
+            .iter()
+            .map(|field| format!("{}\t{}", field.name, field.dtype))
+            .collect::<Vec<_>>()
+            .join("\n"),
+    };
+    println!("{text}");
+    Ok(())
+}
+
+fn count(args: &Args) -> Result<(), Failure> {
+    let (session, _) = open(&file_arg(args, 0, "file")?)?;
+    println!("{}", session.row_count()?);
+    Ok(())
+}
+
+fn run_query(args: &Args) -> Result<(), Failure> {
+    let format = output_format(args.format.as_deref(), &["table", "csv", "json", "ndjson"])?;
+    let sql = args
+        .sql
+        .as_deref()
+        .ok_or_else(|| Failure::Usage("query needs --sql".to_string()))?;
+    let (session, _) = open(&file_arg(args, 0, "file")?)?;
+    let mut lf = session.query(sql)?;
+    if let Some(limit) = args.limit {
+        lf = lf.limit(limit);
+    }
+    let df = lf.collect().map_err(|err| session.explain(err, sql))?;
+    print_frame(df, format)?;
+    Ok(())
+}
+
+fn convert(args: &Args) -> Result<(), Failure> {
+    let input = file_arg(args, 0, "in")?;
+    let output = file_arg(args, 1, "out")?;
+    let extension = Path::new(&output)
+        .extension()
+        .and_then(|value| value.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    let format = export_extension(&extension)
+        .map_err(|_| Failure::Usage(format!("Unsupported output format: .{extension}")))?;
+
+    let (session, _) = open(&input)?;
+    let df = session.collect(args.sql.as_deref().unwrap_or("SELECT * FROM source"))?;
+    write_export(&df, Path::new(&output), format)?;
+    eprintln!("Wrote {} rows to {output}", df.height());
+    Ok(())
+}
+
+fn run(args: impl IntoIterator<Item = String>) -> i32 {
+    let mut args = args.into_iter();
+    let command = args.next();
+    let result = parse_args(args).and_then(|args| match command.as_deref() {
+        Some("schema") => schema(&args),
+        Some("count") => count(&args),
+        Some("query") => run_query(&args),
+        Some("convert") => convert(&args),
+        Some("help" | "--help" | "-h") => {
+            println!("{USAGE}");
+            Ok(())
+        }
+        Some(other) => Err(Failure::Usage(format!("Unknown command: {other}"))),
+        None => Err(Failure::Usage("Missing command".to_string())),
+    });
+
+    match result {
+        Ok(()) => EXIT_OK,
+        Err(Failure::Usage(message)) => {
+            eprintln!("error: {message}\n\n{USAGE}");
+            EXIT_USAGE
+        }
+        Err(Failure::Run(message)) => {
+            eprintln!("error: {message}");
+            EXIT_FAILURE
+        }
+    }
+}
+
+fn main() {
+    std::process::exit(run(std::env::args().skip(1)));
+}
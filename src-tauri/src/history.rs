@@ -0,0 +1,178 @@
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use rusqlite::types::Value;
+use rusqlite::{params, params_from_iter, Connection, Row};
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+
+/// Unpinned entries beyond this count are dropped, oldest first.
+const MAX_ENTRIES: i64 = 5000;
+const DEFAULT_LIMIT: usize = 200;
+
+#[derive(Serialize)]
+pub(crate) struct HistoryEntry {
+    id: i64,
+    sql: String,
+    files: Vec<String>,
+    executed_at_ms: i64,
+    duration_ms: i64,
+    row_count: Option<i64>,
+    error: Option<String>,
+    pinned: bool,
+}
+
+/// One finished `exec_sql` run, successful or not.
+pub(crate) struct QueryRun<'a> {
+    pub(crate) sql: &'a str,
+    pub(crate) files: Vec<String>,
+    pub(crate) duration_ms: u64,
+    pub(crate) row_count: Option<usize>,
+    pub(crate) error: Option<String>,
+}
+
+fn history_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|err: tauri::Error| err.to_string())?;
+    std::fs::create_dir_all(&dir).map_err(|err| err.to_string())?;
+    Ok(dir.join("history.sqlite"))
+}
+
+fn open(app: &AppHandle) -> Result<Connection, String> {
+    let conn = Connection::open(history_path(app)?).map_err(|err| err.to_string())?;
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS query_history (
+             id INTEGER PRIMARY KEY AUTOINCREMENT,
+             sql TEXT NOT NULL,
+             files TEXT NOT NULL,
+             executed_at INTEGER NOT NULL,
+             duration_ms INTEGER NOT NULL,
+             row_count INTEGER,
+             error TEXT,
+             pinned INTEGER NOT NULL DEFAULT 0
+         );
+         CREATE INDEX IF NOT EXISTS query_history_executed_at
+             ON query_history (executed_at);",
+    )
+    .map_err(|err| err.to_string())?;
+    Ok(conn)
+}
+
+fn now_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as i64)
+        .unwrap_or(0)
+}
+
+fn entry_from_row(row: &Row) -> rusqlite::Result<HistoryEntry> {
+    let files: String = row.get("files")?;
+    Ok(HistoryEntry {
+        id: row.get("id")?,
+        sql: row.get("sql")?,
+        files: serde_json::from_str(&files).unwrap_or_default(),
+        executed_at_ms: row.get("executed_at")?,
+        duration_ms: row.get("duration_ms")?,
+        row_count: row.get("row_count")?,
+        error: row.get("error")?,
+        pinned: row.get::<_, i64>("pinned")? != 0,
+    })
+}
+
+pub(crate) fn record(app: &AppHandle, run: QueryRun) -> Result<(), String> {
+    let conn = open(app)?;
+    let files = serde_json::to_string(&run.files).map_err(|err| err.to_string())?;
+    conn.execute(
+        "INSERT INTO query_history (sql, files, executed_at, duration_ms, row_count, error)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![
+            run.sql,
+            files,
+            now_ms(),
+            run.duration_ms as i64,
+            run.row_count.map(|count| count as i64),
+            run.error,
+        ],
+    )
+    .map_err(|err| err.to_string())?;
+    conn.execute(
+        "DELETE FROM query_history WHERE pinned = 0 AND id NOT IN (
+             SELECT id FROM query_history WHERE pinned = 0
+             ORDER BY executed_at DESC LIMIT ?1
+         )",
+        params![MAX_ENTRIES],
+    )
+    .map_err(|err| err.to_string())?;
+    Ok(())
+}
+
+fn like_pattern(term: &str) -> String {
+    let escaped = term
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    format!("%{escaped}%")
+}
+
+/// Pinned entries first, then newest first. Every whitespace-separated term
+/// of `search` must appear in the SQL text, a file path or the error.
+pub(crate) fn list(
+    app: &AppHandle,
+    search: Option<&str>,
+    limit: Option<usize>,
+) -> Result<Vec<HistoryEntry>, String> {
+    let conn = open(app)?;
+    let terms = search
+        .map(|search| {
+            search
+                .split_whitespace()
+                .map(like_pattern)
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+
+    let mut sql = "SELECT * FROM query_history".to_string();
+    let mut values = Vec::new();
+    for (idx, term) in terms.into_iter().enumerate() {
+        sql.push_str(if idx == 0 { " WHERE " } else { " AND " });
+        let param = idx + 1;
+        sql.push_str(&format!(
+            "(sql LIKE ?{param} ESCAPE '\\' OR files LIKE ?{param} ESCAPE '\\' \
+             OR error LIKE ?{param} ESCAPE '\\')"
+        ));
+        values.push(Value::Text(term));
+    }
+    sql.push_str(" ORDER BY pinned DESC, executed_at DESC LIMIT ");
+    sql.push_str(&limit.unwrap_or(DEFAULT_LIMIT).to_string());
+
+    let mut stmt = conn.prepare(&sql).map_err(|err| err.to_string())?;
+    let entries = stmt
+        .query_map(params_from_iter(values), entry_from_row)
+        .map_err(|err| err.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|err| err.to_string())?;
+    Ok(entries)
+}
+
+pub(crate) fn set_pinned(app: &AppHandle, id: i64, pinned: bool) -> Result<(), String> {
+    let conn = open(app)?;
+    let updated = conn
+        .execute(
+            "UPDATE query_history SET pinned = ?1 WHERE id = ?2",
+            params![pinned as i64, id],
+        )
+        .map_err(|err| err.to_string())?;
+    if updated == 0 {
+        return Err(format!("History entry not found: {id}"));
+    }
+    Ok(())
+}
+
+pub(crate) fn delete(app: &AppHandle, id: i64) -> Result<(), String> {
+    let conn = open(app)?;
+    conn.execute("DELETE FROM query_history WHERE id = ?1", params![id])
+        .map_err(|err| err.to_string())?;
+    Ok(())
+}
@@ -1,519 +1,605 @@
-use std::fs::File;
-use std::io::{Cursor, Read};
+mod api;
+mod history;
+mod recent;
+mod saved_queries;
+mod watch;
+mod workspace;
+
+use std::collections::{HashMap, VecDeque};
 use std::path::{Path, PathBuf};
 use std::sync::Mutex;
+use std::time::{Duration, Instant};
 
-use calamine::{open_workbook_auto, Data, Reader};
-use flate2::read::GzDecoder;
-use polars::lazy::dsl::col;
-use polars::prelude::*;
-use polars::sql::SQLContext;
+use lakedrop_core::{
+    fixed_width, http, s3, Completion, Error, ExportOptions, FileKind, FileMetadata, FormatOptions,
+    QueryResult, Session,
+};
 use serde::Serialize;
-use tauri::{AppHandle, Manager, State};
+use tauri::{AppHandle, Emitter, Manager, State};
 
 #[derive(Default)]
 struct AppState {
-    source: Option<LazyFrame>,
-    file_path: Option<PathBuf>,
-    file_kind: Option<FileKind>,
-    sheets: Vec<String>,
-    active_sheet: Option<String>,
-}
-
-#[derive(Clone, Copy, PartialEq, Eq)]
-enum FileKind {
-    Parquet,
-    Csv,
-    JsonLines,
-    Json,
-    Arrow,
-    Excel,
-}
-
-struct FileSpec {
-    kind: FileKind,
-    compressed: bool,
-    extension: String,
-}
-
-#[derive(Serialize)]
-struct FieldInfo {
-    name: String,
-    dtype: String,
+    session: Session,
+    watcher: Option<watch::FileWatcher>,
+    api: Option<api::ApiServer>,
+    /// Queries run through the HTTP API, newest first.
+    api_queries: VecDeque<api::ApiQuery>,
 }
 
 #[derive(Serialize)]
 struct FileMetadataResponse {
-    file_name: String,
-    file_path: String,
-    file_size: u64,
-    row_count: u64,
-    schema: Vec<FieldInfo>,
-    sheets: Vec<String>,
-    active_sheet: Option<String>,
+    #[serde(flatten)]
+    metadata: FileMetadata,
+    /// The schema differs from the one recorded the last time this path
+    /// was opened.
+    schema_changed: bool,
 }
 
-#[derive(Serialize)]
-struct ColumnInfo {
-    name: String,
-    dtype: String,
+impl From<FileMetadata> for FileMetadataResponse {
+    fn from(metadata: FileMetadata) -> Self {
+        Self {
+            metadata,
+            schema_changed: false,
+        }
+    }
 }
 
 #[derive(Serialize)]
-struct QueryResult {
-    columns: Vec<ColumnInfo>,
-    rows: Vec<Vec<serde_json::Value>>,
-    row_count: usize,
-}
-
-fn gzip_magic(path: &Path) -> Result<bool, String> {
-    let mut file = File::open(path).map_err(|err| err.to_string())?;
-    let mut buf = [0u8; 2];
-    let read = file.read(&mut buf).map_err(|err| err.to_string())?;
-    Ok(read == 2 && buf == [0x1f, 0x8b])
-}
-
-fn detect_file_kind(path: &Path) -> Result<FileSpec, String> {
-    let mut compressed = false;
-    let mut ext = path
-        .extension()
-        .and_then(|value| value.to_str())
-        .unwrap_or("")
-        .to_ascii_lowercase();
-
-    if ext == "gz" {
-        compressed = true;
-        ext = path
-            .file_stem()
-            .and_then(|value| Path::new(value).extension())
-            .and_then(|value| value.to_str())
-            .unwrap_or("")
-            .to_ascii_lowercase();
-    }
+struct WorkspaceRestore {
+    file: Option<FileMetadataResponse>,
+    editor: serde_json::Value,
+    /// Files the workspace refers to that no longer exist.
+    missing: Vec<String>,
+    /// Selections that could not be re-applied, such as a deleted sheet.
+    warnings: Vec<String>,
+}
 
-    let kind = match ext.as_str() {
-        "parquet" | "parq" => FileKind::Parquet,
-        "csv" | "tsv" | "txt" => FileKind::Csv,
-        "jsonl" | "ndjson" => FileKind::JsonLines,
-        "json" => FileKind::Json,
-        "arrow" | "feather" | "ipc" => FileKind::Arrow,
-        "xlsx" | "xls" => FileKind::Excel,
-        _ => return Err(format!("Unsupported file type: .{ext}")),
+/// Async so a download runs off the main thread: progress events reach the
+/// webview while it streams, and the state lock is only taken to open the
+/// finished file.
+#[tauri::command]
+async fn scan_file_metadata(
+    path: String,
+    app: AppHandle,
+    state: State<'_, Mutex<AppState>>,
+) -> Result<FileMetadataResponse, Error> {
+    // Parquet is scanned in place with range requests; other formats over
+    // HTTP are downloaded into the cache first.
+    let downloaded = if http::requires_download(&path) {
+        let cache_dir = app
+            .path()
+            .app_cache_dir()
+            .map_err(|err: tauri::Error| err.to_string())?;
+        let url = path.clone();
+        let progress_app = app.clone();
+        let local = tauri::async_runtime::spawn_blocking(move || {
+            http::fetch_cached(&cache_dir, &url, |progress| {
+                let _ = progress_app.emit("download-progress", progress.clone());
+            })
+        })
+        .await
+        .map_err(|err: tauri::Error| err.to_string())??;
+        Some(local)
+    } else {
+        None
     };
 
-    if !compressed {
-        compressed = gzip_magic(path).unwrap_or(false);
-    }
+    let metadata = {
+        let mut guard = state.lock().map_err(|_| "State lock failed")?;
+        let metadata = match downloaded {
+            Some(local) => guard.session.open_downloaded(&path, local)?,
+            None => guard.session.open(&path)?,
+        };
+        guard.watcher = None;
+        metadata
+    };
 
-    Ok(FileSpec {
-        kind,
-        compressed,
-        extension: ext,
+    let schema_changed = recent::record(
+        &app,
+        &path,
+        metadata.file_kind,
+        metadata.file_size,
+        &metadata.schema,
+    )
+    .unwrap_or(false);
+    Ok(FileMetadataResponse {
+        metadata,
+        schema_changed,
     })
 }
 
-fn schema_to_fields(schema: &Schema) -> Vec<FieldInfo> {
-    schema
-        .iter()
-        .map(|(name, dtype)| FieldInfo {
-            name: name.to_string(),
-            dtype: format!("{dtype:?}"),
-        })
-        .collect()
-}
-
-fn excel_cell_to_string(cell: &Data) -> Option<String> {
-    match cell {
-        Data::Empty => None,
-        Data::String(value) => Some(value.to_string()),
-        Data::Float(value) => Some(value.to_string()),
-        Data::Int(value) => Some(value.to_string()),
-        Data::Bool(value) => Some(value.to_string()),
-        Data::DateTime(value) => Some(value.to_string()),
-        Data::DateTimeIso(value) => Some(value.to_string()),
-        Data::DurationIso(value) => Some(value.to_string()),
-        Data::Error(value) => Some(format!("{value:?}")),
-    }
+#[tauri::command]
+fn select_excel_sheet(
+    sheet: String,
+    state: State<'_, Mutex<AppState>>,
+) -> Result<FileMetadataResponse, Error> {
+    let mut guard = state.lock().map_err(|_| "State lock failed")?;
+    guard.session.select_excel_sheet(&sheet).map(Into::into)
 }
 
-fn load_excel_sheet(
-    path: &Path,
-    sheet_name: Option<String>,
-) -> Result<(DataFrame, Vec<String>, String), String> {
-    let mut workbook = open_workbook_auto(path).map_err(|err| err.to_string())?;
-    let sheets = workbook.sheet_names().to_vec();
-    let active = sheet_name
-        .or_else(|| sheets.first().cloned())
-        .ok_or("No sheets found in workbook")?;
-    let range = workbook
-        .worksheet_range(&active)
-        .map_err(|err| err.to_string())?;
-    let mut rows = range.rows();
-    let header_row = rows.next();
-    let mut headers = header_row
-        .map(|row| {
-            row.iter()
-                .enumerate()
-                .map(|(idx, cell)| {
-                    excel_cell_to_string(cell)
-                        .filter(|value| !value.trim().is_empty())
-                        .unwrap_or_else(|| format!("col_{}", idx + 1))
-                })
-                .collect::<Vec<_>>()
-        })
-        .unwrap_or_default();
-
-    let mut columns: Vec<Vec<Option<String>>> = vec![Vec::new(); headers.len()];
-    for row in rows {
-        if row.len() > headers.len() {
-            let start = headers.len();
-            headers.extend((start..row.len()).map(|idx| format!("col_{}", idx + 1)));
-            columns.extend((start..row.len()).map(|_| Vec::new()));
-        }
-        for (idx, col) in columns.iter_mut().enumerate() {
-            let value = row.get(idx).and_then(excel_cell_to_string);
-            col.push(value);
-        }
-    }
-
-    let series = headers
-        .iter()
-        .zip(columns)
-        .map(|(name, values)| Series::new(name, values))
-        .collect::<Vec<_>>();
-
-    let df = DataFrame::new(series).map_err(|err| err.to_string())?;
-    Ok((df, sheets, active))
-}
-
-fn load_lazy_frame(path: &Path, spec: &FileSpec) -> Result<LazyFrame, String> {
-    match (spec.kind, spec.compressed) {
-        (FileKind::Parquet, false) => LazyFrame::scan_parquet(path, ScanArgsParquet::default())
-            .map_err(|err| err.to_string()),
-        (FileKind::Csv, false) => LazyCsvReader::new(path)
-            .with_separator(if spec.extension == "tsv" { b'\t' } else { b',' })
-            .with_try_parse_dates(true)
-            .finish()
-            .map_err(|err| err.to_string()),
-        (FileKind::JsonLines, false) => {
-            let file = File::open(path).map_err(|err| err.to_string())?;
-            let df = JsonLineReader::new(file)
-                .finish()
-                .map_err(|err| err.to_string())?;
-            Ok(df.lazy())
-        }
-        (FileKind::Json, false) => {
-            let file = File::open(path).map_err(|err| err.to_string())?;
-            let df = JsonReader::new(file)
-                .with_json_format(JsonFormat::Json)
-                .finish()
-                .map_err(|err| err.to_string())?;
-            Ok(df.lazy())
-        }
-        (FileKind::Arrow, false) => {
-            let file = File::open(path).map_err(|err| err.to_string())?;
-            let df = IpcReader::new(file)
-                .finish()
-                .map_err(|err| err.to_string())?;
-            Ok(df.lazy())
-        }
-        (FileKind::Excel, false) => {
-            let (df, _, _) = load_excel_sheet(path, None)?;
-            Ok(df.lazy())
-        }
-        (FileKind::Csv, true) => {
-            let file = File::open(path).map_err(|err| err.to_string())?;
-            let mut decoder = GzDecoder::new(file);
-            let mut buffer = Vec::new();
-            decoder
-                .read_to_end(&mut buffer)
-                .map_err(|err| err.to_string())?;
-            let cursor = Cursor::new(buffer);
-            let reader = CsvReadOptions::default()
-                .map_parse_options(|options: CsvParseOptions| {
-                    options
-                        .with_separator(if spec.extension == "tsv" { b'\t' } else { b',' })
-                        .with_try_parse_dates(true)
-                })
-                .into_reader_with_file_handle(cursor);
-            let df = reader.finish().map_err(|err| err.to_string())?;
-            Ok(df.lazy())
-        }
-        (FileKind::JsonLines, true) => {
-            let file = File::open(path).map_err(|err| err.to_string())?;
-            let mut decoder = GzDecoder::new(file);
-            let mut buffer = Vec::new();
-            decoder
-                .read_to_end(&mut buffer)
-                .map_err(|err| err.to_string())?;
-            let cursor = Cursor::new(buffer);
-            let df = JsonLineReader::new(cursor)
-                .finish()
-                .map_err(|err| err.to_string())?;
-            Ok(df.lazy())
-        }
-        (FileKind::Json, true) => {
-            let file = File::open(path).map_err(|err| err.to_string())?;
-            let mut decoder = GzDecoder::new(file);
-            let mut buffer = Vec::new();
-            decoder
-                .read_to_end(&mut buffer)
-                .map_err(|err| err.to_string())?;
-            let cursor = Cursor::new(buffer);
-            let df = JsonReader::new(cursor)
-                .with_json_format(JsonFormat::Json)
-                .finish()
-                .map_err(|err| err.to_string())?;
-            Ok(df.lazy())
-        }
-        (_, true) => Err("Compressed file is not supported for this format".to_string()),
-    }
+#[tauri::command]
+fn select_sqlite_table(
+    table: String,
+    state: State<'_, Mutex<AppState>>,
+) -> Result<FileMetadataResponse, Error> {
+    let mut guard = state.lock().map_err(|_| "State lock failed")?;
+    guard.session.select_sqlite_table(&table).map(Into::into)
 }
 
-fn lazy_row_count(lf: &LazyFrame) -> Result<u64, String> {
-    let df = lf
-        .clone()
-        .select([col("*").len()])
-        .collect()
-        .map_err(|err| err.to_string())?;
-    let series = df.get_columns().get(0).ok_or("Missing count")?;
-    let value = series.get(0).map_err(|err| err.to_string())?;
-    let count = match value {
-        AnyValue::UInt64(value) => value,
-        AnyValue::UInt32(value) => value as u64,
-        AnyValue::Int64(value) => value as u64,
-        AnyValue::Int32(value) => value as u64,
-        _ => 0,
-    };
-    Ok(count)
-}
-
-fn any_value_to_json(value: AnyValue) -> serde_json::Value {
-    match value {
-        AnyValue::Null => serde_json::Value::Null,
-        AnyValue::Boolean(value) => serde_json::Value::Bool(value),
-        AnyValue::Int8(value) => serde_json::Value::from(value),
-        AnyValue::Int16(value) => serde_json::Value::from(value),
-        AnyValue::Int32(value) => serde_json::Value::from(value),
-        AnyValue::Int64(value) => serde_json::Value::from(value),
-        AnyValue::UInt8(value) => serde_json::Value::from(value),
-        AnyValue::UInt16(value) => serde_json::Value::from(value),
-        AnyValue::UInt32(value) => serde_json::Value::from(value),
-        AnyValue::UInt64(value) => serde_json::Value::from(value),
-        AnyValue::Float32(value) => serde_json::Value::from(value as f64),
-        AnyValue::Float64(value) => serde_json::Value::from(value),
-        AnyValue::String(value) => serde_json::Value::String(value.to_string()),
-        AnyValue::StringOwned(value) => serde_json::Value::String(value.to_string()),
-        AnyValue::Binary(value) => serde_json::Value::String(format!("{value:?}")),
-        AnyValue::BinaryOwned(value) => serde_json::Value::String(format!("{value:?}")),
-        AnyValue::Date(value) => serde_json::Value::String(value.to_string()),
-        AnyValue::Datetime(value, _, _) => serde_json::Value::String(value.to_string()),
-        AnyValue::Time(value) => serde_json::Value::String(value.to_string()),
-        AnyValue::Duration(value, _) => serde_json::Value::String(value.to_string()),
-        AnyValue::List(_) => serde_json::Value::String(value.to_string()),
-        _ => serde_json::Value::String(value.to_string()),
-    }
+#[tauri::command]
+fn select_table_version(
+    version: String,
+    state: State<'_, Mutex<AppState>>,
+) -> Result<FileMetadataResponse, Error> {
+    let mut guard = state.lock().map_err(|_| "State lock failed")?;
+    guard.session.select_version(&version).map(Into::into)
 }
 
 #[tauri::command]
-fn scan_file_metadata(
-    path: String,
+fn select_archive_member(
+    member: String,
     state: State<'_, Mutex<AppState>>,
-) -> Result<FileMetadataResponse, String> {
-    let path = PathBuf::from(path);
-    let spec = detect_file_kind(&path)?;
-    let file_size = std::fs::metadata(&path)
-        .map(|meta| meta.len())
-        .unwrap_or(0);
-
-    let (lf, sheets, active_sheet, row_count, schema) = if spec.kind == FileKind::Excel {
-        let (df, sheets, active_sheet) = load_excel_sheet(&path, None)?;
-        let schema = df.schema();
-        let row_count = df.height() as u64;
-        (df.lazy(), sheets, Some(active_sheet), row_count, schema)
-    } else {
-        let mut lf = load_lazy_frame(&path, &spec)?;
-        let schema = lf
-            .schema()
-            .map_err(|err| err.to_string())?
-            .as_ref()
-            .clone();
-        let row_count = lazy_row_count(&lf).unwrap_or(0);
-        (lf, Vec::new(), None, row_count, schema)
-    };
+) -> Result<FileMetadataResponse, Error> {
+    let mut guard = state.lock().map_err(|_| "State lock failed")?;
+    guard.session.select_archive_member(&member).map(Into::into)
+}
 
-    let file_name = path
-        .file_name()
-        .and_then(|value| value.to_str())
-        .unwrap_or("data")
-        .to_string();
-
-    let response = FileMetadataResponse {
-        file_name,
-        file_path: path.display().to_string(),
-        file_size,
-        row_count,
-        schema: schema_to_fields(&schema),
-        sheets,
-        active_sheet,
-    };
+/// Registers every data file of the open archive as its own SQL table, named
+/// after the member's file stem. `source` keeps pointing at the selected member.
+#[tauri::command]
+fn register_archive_members(state: State<'_, Mutex<AppState>>) -> Result<Vec<String>, Error> {
+    let mut guard = state.lock().map_err(|_| "State lock failed")?;
+    guard.session.register_archive_members()
+}
 
+/// Registers every Excel Table and named range of the open workbook as a SQL
+/// table under its own name. `source` keeps pointing at the selected sheet.
+#[tauri::command]
+fn register_excel_ranges(state: State<'_, Mutex<AppState>>) -> Result<Vec<String>, Error> {
     let mut guard = state.lock().map_err(|_| "State lock failed")?;
-    guard.source = Some(lf);
-    guard.file_path = Some(path);
-    guard.file_kind = Some(spec.kind);
-    guard.sheets = response.sheets.clone();
-    guard.active_sheet = response.active_sheet.clone();
+    guard.session.register_excel_ranges()
+}
 
-    Ok(response)
+/// Re-reads the open text file as fixed-width columns. An empty column list
+/// infers the layout from the header or ruler line; the resolved layout is
+/// returned so it can be edited and applied again.
+#[tauri::command]
+fn set_fixed_width_layout(
+    options: fixed_width::FixedWidthOptions,
+    state: State<'_, Mutex<AppState>>,
+) -> Result<FileMetadataResponse, Error> {
+    let mut guard = state.lock().map_err(|_| "State lock failed")?;
+    guard
+        .session
+        .set_fixed_width_layout(&options)
+        .map(Into::into)
 }
 
+/// Payload of the `source-changed` event.
+#[derive(Clone, Serialize)]
+struct SourceChanged {
+    file_path: String,
+    file_size: u64,
+    /// `None` when the reload failed; the previous source stays active.
+    row_count: Option<u64>,
+    error: Option<String>,
+}
+
+/// Rebuilds `source` after the watched file changed on disk. Returns `None`
+/// once another file has been opened.
+fn reload_source(app: &AppHandle, path: &Path) -> Result<Option<u64>, Error> {
+    let state = app.state::<Mutex<AppState>>();
+    let mut guard = state.lock().map_err(|_| "State lock failed")?;
+    if guard.session.file_path() != Some(path) {
+        return Ok(None);
+    }
+    guard.session.reload().map(Some)
+}
+
+/// Starts or stops reloading the current file whenever it changes on disk.
+/// Each reload emits `source-changed`. Only local flat files can be watched;
+/// workbooks, databases and table formats keep a selection that a plain
+/// reload would lose.
 #[tauri::command]
-fn select_excel_sheet(
-    sheet: String,
+fn watch_file(
+    enabled: bool,
+    interval_ms: Option<u64>,
+    app: AppHandle,
     state: State<'_, Mutex<AppState>>,
-) -> Result<FileMetadataResponse, String> {
+) -> Result<(), String> {
     let mut guard = state.lock().map_err(|_| "State lock failed")?;
+    if !enabled {
+        guard.watcher = None;
+        return Ok(());
+    }
     let path = guard
-        .file_path
-        .as_ref()
+        .session
+        .file_path()
+        .map(Path::to_path_buf)
         .ok_or("No file loaded. Drag a file to begin.")?;
-    if guard.file_kind != Some(FileKind::Excel) {
-        return Err("Current file is not an Excel workbook.".to_string());
+    if guard
+        .session
+        .source_path()
+        .is_some_and(|source| s3::is_s3_uri(source) || http::is_http_url(source))
+    {
+        return Err("Only local files can be watched.".to_string());
+    }
+    if !matches!(
+        guard.session.file_kind(),
+        Some(
+            FileKind::Csv
+                | FileKind::JsonLines
+                | FileKind::Json
+                | FileKind::Parquet
+                | FileKind::Arrow
+                | FileKind::Avro
+                | FileKind::Orc
+                | FileKind::FixedWidth
+        )
+    ) {
+        return Err("This file type cannot be watched.".to_string());
+    }
+    if guard
+        .watcher
+        .as_ref()
+        .is_some_and(|watcher| watcher.path() == path)
+    {
+        return Ok(());
     }
 
-    let (df, sheets, active_sheet) = load_excel_sheet(path, Some(sheet))?;
-    let schema = df.schema();
-    let row_count = df.height() as u64;
-    let response = FileMetadataResponse {
-        file_name: path
-            .file_name()
-            .and_then(|value| value.to_str())
-            .unwrap_or("data")
-            .to_string(),
-        file_path: path.display().to_string(),
-        file_size: std::fs::metadata(path)
-            .map(|meta| meta.len())
-            .unwrap_or(0),
-        row_count,
-        schema: schema_to_fields(&schema),
-        sheets,
-        active_sheet: Some(active_sheet),
-    };
-
-    guard.source = Some(df.lazy());
-    guard.sheets = response.sheets.clone();
-    guard.active_sheet = response.active_sheet.clone();
-
-    Ok(response)
+    let interval = interval_ms
+        .map(Duration::from_millis)
+        .unwrap_or(watch::DEFAULT_INTERVAL);
+    let watched = path.clone();
+    guard.watcher = Some(watch::watch(path, interval, move || {
+        let (row_count, error) = match reload_source(&app, &watched) {
+            Ok(None) => return false,
+            Ok(Some(row_count)) => (Some(row_count), None),
+            Err(err) => (None, Some(err.message)),
+        };
+        let _ = app.emit(
+            "source-changed",
+            SourceChanged {
+                file_path: watched.display().to_string(),
+                file_size: std::fs::metadata(&watched)
+                    .map(|meta| meta.len())
+                    .unwrap_or(0),
+                row_count,
+                error,
+            },
+        );
+        true
+    }));
+    Ok(())
 }
 
 #[tauri::command]
 fn exec_sql(
     sql: String,
     max_rows: Option<usize>,
+    app: AppHandle,
     state: State<'_, Mutex<AppState>>,
-) -> Result<QueryResult, String> {
+) -> Result<QueryResult, Error> {
     let guard = state.lock().map_err(|_| "State lock failed")?;
-    let source = guard
-        .source
-        .as_ref()
-        .ok_or("No file loaded. Drag a file to begin.")?;
-
-    let mut ctx = SQLContext::new();
-    ctx.register("source", source.clone());
-    let df = ctx
-        .execute(&sql)
-        .map_err(|err| err.to_string())?
-        .collect()
-        .map_err(|err| err.to_string())?;
+    let started = Instant::now();
+    let result = guard.session.execute(&sql, max_rows);
+    let duration_ms = started.elapsed().as_millis() as u64;
+    let files = guard.session.query_sources(&sql);
+    drop(guard);
+
+    // History is best effort: a broken store must not fail the query.
+    let _ = history::record(
+        &app,
+        history::QueryRun {
+            sql: &sql,
+            files,
+            duration_ms,
+            row_count: result.as_ref().ok().map(|result| result.total_rows),
+            error: result.as_ref().err().map(|err| err.message.clone()),
+        },
+    );
+
+    result
+}
 
-    let df = if let Some(max_rows) = max_rows {
-        df.head(Some(max_rows))
-    } else {
-        df
-    };
+/// Re-runs the editor query for tail mode. Same as `exec_sql`, but the
+/// periodic re-runs are not written to the query history.
+#[tauri::command]
+fn tail_sql(
+    sql: String,
+    max_rows: Option<usize>,
+    state: State<'_, Mutex<AppState>>,
+) -> Result<QueryResult, Error> {
+    let guard = state.lock().map_err(|_| "State lock failed")?;
+    guard.session.execute(&sql, max_rows)
+}
 
-    let columns = df
-        .schema()
-        .iter_fields()
-        .map(|field| ColumnInfo {
-            name: field.name().to_string(),
-            dtype: format!("{:?}", field.data_type()),
-        })
-        .collect::<Vec<_>>();
-
-    let row_count = df.height();
-    let mut rows = Vec::with_capacity(row_count);
-    for row_idx in 0..row_count {
-        let mut row = Vec::with_capacity(df.width());
-        for series in df.get_columns() {
-            let value = series.get(row_idx).map_err(|err| err.to_string())?;
-            row.push(any_value_to_json(value));
-        }
-        rows.push(row);
-    }
+/// Completions for the editor at `cursor_offset`, a UTF-16 offset into
+/// `sql` as Monaco's `getOffsetAt` returns it.
+#[tauri::command]
+fn complete_sql(
+    sql: String,
+    cursor_offset: usize,
+    state: State<'_, Mutex<AppState>>,
+) -> Result<Vec<Completion>, String> {
+    let guard = state.lock().map_err(|_| "State lock failed")?;
+    Ok(guard.session.complete(&sql, cursor_offset))
+}
 
-    Ok(QueryResult {
-        columns,
-        rows,
-        row_count,
-    })
+/// Pretty-prints the editor SQL, or fails with where it stops parsing.
+#[tauri::command]
+fn format_sql(sql: String, options: Option<FormatOptions>) -> Result<String, Error> {
+    lakedrop_core::format_sql(&sql, &options.unwrap_or_default())
 }
 
 #[tauri::command]
 fn export_query(
     sql: String,
     path: String,
-    format: String,
+    options: ExportOptions,
     state: State<'_, Mutex<AppState>>,
-) -> Result<(), String> {
+) -> Result<(), Error> {
     let guard = state.lock().map_err(|_| "State lock failed")?;
-    let source = guard
-        .source
-        .as_ref()
-        .ok_or("No file loaded. Drag a file to begin.")?;
-    let mut ctx = SQLContext::new();
-    ctx.register("source", source.clone());
-    let df = ctx
-        .execute(&sql)
-        .map_err(|err| err.to_string())?
-        .collect()
-        .map_err(|err| err.to_string())?;
-
-    let path = PathBuf::from(path);
-    match format.as_str() {
-        "csv" => {
-            let mut file = File::create(path).map_err(|err| err.to_string())?;
-            let mut df = df;
-            CsvWriter::new(&mut file)
-                .finish(&mut df)
-                .map_err(|err| err.to_string())?;
+    guard.session.export(&sql, Path::new(&path), &options)
+}
+
+#[tauri::command]
+fn list_query_history(
+    search: Option<String>,
+    limit: Option<usize>,
+    app: AppHandle,
+) -> Result<Vec<history::HistoryEntry>, String> {
+    history::list(&app, search.as_deref(), limit)
+}
+
+#[tauri::command]
+fn pin_query_history(id: i64, pinned: bool, app: AppHandle) -> Result<(), String> {
+    history::set_pinned(&app, id, pinned)
+}
+
+#[tauri::command]
+fn delete_query_history(id: i64, app: AppHandle) -> Result<(), String> {
+    history::delete(&app, id)
+}
+
+#[tauri::command]
+fn list_recent_files(app: AppHandle) -> Result<Vec<recent::RecentFile>, String> {
+    recent::list(&app)
+}
+
+#[tauri::command]
+fn pin_recent_file(path: String, pinned: bool, app: AppHandle) -> Result<(), String> {
+    recent::set_pinned(&app, &path, pinned)
+}
+
+/// Forgets unpinned files that no longer exist, plus those not opened within
+/// `older_than_days` when given. Returns the number removed.
+#[tauri::command]
+fn prune_recent_files(older_than_days: Option<u32>, app: AppHandle) -> Result<usize, String> {
+    recent::prune(&app, older_than_days)
+}
+
+#[tauri::command]
+fn list_saved_queries(app: AppHandle) -> Result<Vec<saved_queries::SavedQuery>, String> {
+    saved_queries::list(&app)
+}
+
+#[tauri::command]
+fn save_query(
+    query: saved_queries::SavedQuery,
+    app: AppHandle,
+) -> Result<saved_queries::SavedQuery, String> {
+    saved_queries::save(&app, query)
+}
+
+#[tauri::command]
+fn delete_saved_query(id: String, app: AppHandle) -> Result<(), String> {
+    saved_queries::delete(&app, &id)
+}
+
+/// Fills in a saved query's parameters, checks column parameters against the
+/// current source schema, then runs it like any other query.
+#[tauri::command]
+fn run_saved_query(
+    id: String,
+    params: HashMap<String, String>,
+    max_rows: Option<usize>,
+    app: AppHandle,
+    state: State<'_, Mutex<AppState>>,
+) -> Result<QueryResult, Error> {
+    let query = saved_queries::find(&app, &id)?;
+    let schema = state
+        .lock()
+        .map_err(|_| "State lock failed")?
+        .session
+        .schema()?;
+    let sql = saved_queries::render(&query, &params, &schema)?;
+    exec_sql(sql, max_rows, app, state)
+}
+
+#[tauri::command]
+fn export_saved_queries(path: String, app: AppHandle) -> Result<usize, String> {
+    saved_queries::export(&app, Path::new(&path))
+}
+
+#[tauri::command]
+fn import_saved_queries(path: String, app: AppHandle) -> Result<usize, String> {
+    saved_queries::import(&app, Path::new(&path))
+}
+
+/// Writes the current session to a `.lakedrop.json` workspace file, adding
+/// the suffix when `path` lacks it, and returns the path written.
+#[tauri::command]
+fn save_workspace(
+    path: String,
+    editor: serde_json::Value,
+    state: State<'_, Mutex<AppState>>,
+) -> Result<String, String> {
+    let guard = state.lock().map_err(|_| "State lock failed")?;
+    let session = &guard.session;
+    let workspace_path = workspace::with_suffix(Path::new(&path));
+    let workspace_path = workspace_path.as_path();
+    let source = session
+        .source_path()
+        .zip(session.file_kind())
+        .map(|(source, kind)| workspace::WorkspaceSource {
+            relative_path: workspace::relative_to(workspace_path, source),
+            path: source.to_string(),
+            kind,
+            active_sheet: session.active_sheet().map(str::to_string),
+            active_version: session.active_version().map(str::to_string),
+            fixed_width: session.fixed_width().cloned(),
+            tables: session.table_names(),
+        });
+    workspace::write(
+        workspace_path,
+        &workspace::Workspace {
+            version: workspace::WORKSPACE_VERSION,
+            source,
+            s3: session.s3_settings().clone(),
+            editor,
+        },
+    )?;
+    Ok(workspace_path.display().to_string())
+}
+
+/// Rebuilds a saved session through the same commands the UI uses: scan the
+/// file, then re-apply the sheet/table/member, version, fixed-width layout
+/// and registered tables.
+#[tauri::command]
+async fn open_workspace(
+    path: String,
+    app: AppHandle,
+    state: State<'_, Mutex<AppState>>,
+) -> Result<WorkspaceRestore, Error> {
+    let workspace_path = Path::new(&path);
+    let saved = workspace::read(workspace_path)?;
+    state
+        .lock()
+        .map_err(|_| "State lock failed")?
+        .session
+        .set_s3_settings(saved.s3.clone());
+
+    let mut restore = WorkspaceRestore {
+        file: None,
+        editor: saved.editor,
+        missing: Vec::new(),
+        warnings: Vec::new(),
+    };
+    let Some(source) = saved.source else {
+        return Ok(restore);
+    };
+    let Some(file_path) = workspace::resolve(workspace_path, &source) else {
+        restore.missing.push(source.path);
+        return Ok(restore);
+    };
+
+    let mut response = scan_file_metadata(file_path, app, state.clone()).await?;
+    let mut apply = |result: Result<FileMetadataResponse, Error>, what: String| match result {
+        Ok(next) => response = next,
+        Err(err) => restore.warnings.push(format!("{what}: {err}")),
+    };
+
+    if let Some(sheet) = source.active_sheet.clone() {
+        let selected = match source.kind {
+            FileKind::Excel => Some(select_excel_sheet(sheet.clone(), state.clone())),
+            FileKind::Sqlite => Some(select_sqlite_table(sheet.clone(), state.clone())),
+            FileKind::Archive => Some(select_archive_member(sheet.clone(), state.clone())),
+            _ => None,
+        };
+        if let Some(result) = selected {
+            apply(result, sheet);
         }
-        "xlsx" => {
-            let mut book = umya_spreadsheet::new_file();
-            let sheet = book
-                .get_sheet_by_name_mut("Sheet1")
-                .ok_or("Missing sheet")?;
-            for (col_idx, name) in df.get_column_names().iter().enumerate() {
-                sheet
-                    .get_cell_mut(((col_idx + 1) as u32, 1u32))
-                    .set_value(name.to_string());
-            }
-            for row_idx in 0..df.height() {
-                for (col_idx, series) in df.get_columns().iter().enumerate() {
-                    let value = series.get(row_idx).map_err(|err| err.to_string())?;
-                    sheet
-                        .get_cell_mut(((col_idx + 1) as u32, (row_idx + 2) as u32))
-                        .set_value(value.to_string());
+    }
+    if let Some(version) = source.active_version.clone() {
+        if matches!(source.kind, FileKind::Delta | FileKind::Iceberg) {
+            apply(select_table_version(version.clone(), state.clone()), version);
+        }
+    }
+    if source.kind == FileKind::FixedWidth {
+        if let Some(options) = source.fixed_width.clone() {
+            apply(
+                set_fixed_width_layout(options, state.clone()),
+                "Fixed-width layout".to_string(),
+            );
+        }
+    }
+
+    if !source.tables.is_empty() {
+        let registered = match source.kind {
+            FileKind::Archive => register_archive_members(state.clone()),
+            FileKind::Excel => register_excel_ranges(state.clone()),
+            _ => Ok(Vec::new()),
+        };
+        match registered {
+            Ok(tables) => {
+                for table in source.tables.iter().filter(|table| !tables.contains(table)) {
+                    restore.warnings.push(format!("Table no longer available: {table}"));
                 }
+                response.metadata.tables = tables;
             }
-            umya_spreadsheet::writer::xlsx::write(&book, path)
-                .map_err(|err| err.to_string())?;
+            Err(err) => restore.warnings.push(err.message),
         }
-        _ => return Err("Unsupported export format".to_string()),
     }
 
+    restore.file = Some(response);
+    Ok(restore)
+}
+
+/// Starts the localhost query API, replacing a running one. A token is
+/// generated unless one is given.
+#[tauri::command]
+fn start_api_server(
+    port: Option<u16>,
+    token: Option<String>,
+    app: AppHandle,
+    state: State<'_, Mutex<AppState>>,
+) -> Result<api::ApiStatus, String> {
+    let mut guard = state.lock().map_err(|_| "State lock failed")?;
+    // Release the port before binding again.
+    guard.api = None;
+    let server = api::start(app, port.unwrap_or(api::DEFAULT_PORT), token)?;
+    let status = server.status();
+    guard.api = Some(server);
+    Ok(status)
+}
+
+#[tauri::command]
+fn stop_api_server(state: State<'_, Mutex<AppState>>) -> Result<(), String> {
+    let mut guard = state.lock().map_err(|_| "State lock failed")?;
+    guard.api = None;
+    Ok(())
+}
+
+#[tauri::command]
+fn api_server_status(state: State<'_, Mutex<AppState>>) -> Result<api::ApiStatus, String> {
+    let guard = state.lock().map_err(|_| "State lock failed")?;
+    Ok(guard
+        .api
+        .as_ref()
+        .map(api::ApiServer::status)
+        .unwrap_or_default())
+}
+
+#[tauri::command]
+fn list_api_queries(state: State<'_, Mutex<AppState>>) -> Result<Vec<api::ApiQuery>, String> {
+    let guard = state.lock().map_err(|_| "State lock failed")?;
+    Ok(guard.api_queries.iter().cloned().collect())
+}
+
+#[tauri::command]
+fn get_s3_settings(state: State<'_, Mutex<AppState>>) -> Result<s3::S3Settings, String> {
+    let guard = state.lock().map_err(|_| "State lock failed")?;
+    Ok(guard.session.s3_settings().clone())
+}
+
+#[tauri::command]
+fn set_s3_settings(
+    settings: s3::S3Settings,
+    state: State<'_, Mutex<AppState>>,
+) -> Result<(), String> {
+    let mut guard = state.lock().map_err(|_| "State lock failed")?;
+    guard.session.set_s3_settings(settings);
     Ok(())
 }
 
@@ -561,9 +647,39 @@ pub fn run() {
         .invoke_handler(tauri::generate_handler![
             scan_file_metadata,
             select_excel_sheet,
+            select_sqlite_table,
+            select_table_version,
+            select_archive_member,
+            register_archive_members,
+            register_excel_ranges,
+            set_fixed_width_layout,
             resolve_sample_path,
             exec_sql,
-            export_query
+            tail_sql,
+            complete_sql,
+            format_sql,
+            watch_file,
+            list_query_history,
+            pin_query_history,
+            delete_query_history,
+            list_recent_files,
+            pin_recent_file,
+            prune_recent_files,
+            list_saved_queries,
+            save_query,
+            delete_saved_query,
+            run_saved_query,
+            export_saved_queries,
+            import_saved_queries,
+            save_workspace,
+            open_workspace,
+            export_query,
+            start_api_server,
+            stop_api_server,
+            api_server_status,
+            list_api_queries,
+            get_s3_settings,
+            set_s3_settings
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
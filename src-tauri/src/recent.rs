@@ -0,0 +1,163 @@
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use lakedrop_core::http::{fnv1a, is_http_url};
+use lakedrop_core::s3::is_s3_uri;
+use lakedrop_core::{FieldInfo, FileKind};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+/// Unpinned entries beyond this count are forgotten, least recent first.
+const MAX_RECENT: usize = 100;
+const MS_PER_DAY: i64 = 86_400_000;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct RecentFile {
+    /// Local path or remote URI, as opened.
+    pub(crate) path: String,
+    pub(crate) kind: FileKind,
+    pub(crate) size: u64,
+    pub(crate) modified_ms: Option<i64>,
+    /// Hash of the column names and types seen on the last open.
+    pub(crate) schema_fingerprint: String,
+    pub(crate) last_opened_ms: i64,
+    #[serde(default)]
+    pub(crate) pinned: bool,
+    /// Filled in when listing; never stored.
+    #[serde(default, skip_deserializing)]
+    pub(crate) missing: bool,
+}
+
+fn index_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|err: tauri::Error| err.to_string())?;
+    std::fs::create_dir_all(&dir).map_err(|err| err.to_string())?;
+    Ok(dir.join("recent_files.json"))
+}
+
+fn load(app: &AppHandle) -> Result<Vec<RecentFile>, String> {
+    let path = index_path(app)?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let text = std::fs::read_to_string(&path).map_err(|err| err.to_string())?;
+    serde_json::from_str(&text).map_err(|err| err.to_string())
+}
+
+fn store(app: &AppHandle, entries: &[RecentFile]) -> Result<(), String> {
+    let text = serde_json::to_string_pretty(entries).map_err(|err| err.to_string())?;
+    std::fs::write(index_path(app)?, text).map_err(|err| err.to_string())
+}
+
+fn now_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as i64)
+        .unwrap_or(0)
+}
+
+fn is_missing(path: &str) -> bool {
+    !is_s3_uri(path) && !is_http_url(path) && !Path::new(path).exists()
+}
+
+fn modified_ms(path: &str) -> Option<i64> {
+    std::fs::metadata(path)
+        .and_then(|meta| meta.modified())
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map(|elapsed| elapsed.as_millis() as i64)
+}
+
+fn schema_fingerprint(schema: &[FieldInfo]) -> String {
+    let columns = schema
+        .iter()
+        .map(|field| format!("{}:{}", field.name, field.dtype))
+        .collect::<Vec<_>>()
+        .join("\n");
+    format!("{:016x}", fnv1a(&columns))
+}
+
+/// Pinned first, then most recently opened.
+fn sort(entries: &mut [RecentFile]) {
+    entries.sort_by(|a, b| {
+        b.pinned
+            .cmp(&a.pinned)
+            .then(b.last_opened_ms.cmp(&a.last_opened_ms))
+    });
+}
+
+/// Records an open and reports whether the schema differs from the one seen
+/// the last time this path was opened.
+pub(crate) fn record(
+    app: &AppHandle,
+    path: &str,
+    kind: FileKind,
+    size: u64,
+    schema: &[FieldInfo],
+) -> Result<bool, String> {
+    let mut entries = load(app)?;
+    let fingerprint = schema_fingerprint(schema);
+    let previous = entries.iter().position(|entry| entry.path == path);
+    let schema_changed = previous
+        .map(|idx| entries[idx].schema_fingerprint != fingerprint)
+        .unwrap_or(false);
+    let pinned = previous.map(|idx| entries[idx].pinned).unwrap_or(false);
+    if let Some(idx) = previous {
+        entries.remove(idx);
+    }
+
+    entries.push(RecentFile {
+        path: path.to_string(),
+        kind,
+        size,
+        modified_ms: modified_ms(path),
+        schema_fingerprint: fingerprint,
+        last_opened_ms: now_ms(),
+        pinned,
+        missing: false,
+    });
+    sort(&mut entries);
+    let mut unpinned = 0;
+    entries.retain(|entry| {
+        unpinned += usize::from(!entry.pinned);
+        entry.pinned || unpinned <= MAX_RECENT
+    });
+    store(app, &entries)?;
+    Ok(schema_changed)
+}
+
+pub(crate) fn list(app: &AppHandle) -> Result<Vec<RecentFile>, String> {
+    let mut entries = load(app)?;
+    for entry in &mut entries {
+        entry.missing = is_missing(&entry.path);
+    }
+    sort(&mut entries);
+    Ok(entries)
+}
+
+pub(crate) fn set_pinned(app: &AppHandle, path: &str, pinned: bool) -> Result<(), String> {
+    let mut entries = load(app)?;
+    let entry = entries
+        .iter_mut()
+        .find(|entry| entry.path == path)
+        .ok_or_else(|| format!("Not in recent files: {path}"))?;
+    entry.pinned = pinned;
+    store(app, &entries)
+}
+
+/// Drops unpinned entries whose file is gone or that were last opened more
+/// than `older_than_days` ago. Returns how many were removed.
+pub(crate) fn prune(app: &AppHandle, older_than_days: Option<u32>) -> Result<usize, String> {
+    let mut entries = load(app)?;
+    let before = entries.len();
+    let cutoff = older_than_days.map(|days| now_ms() - i64::from(days) * MS_PER_DAY);
+    entries.retain(|entry| {
+        entry.pinned
+            || !(is_missing(&entry.path)
+                || cutoff.is_some_and(|cutoff| entry.last_opened_ms < cutoff))
+    });
+    store(app, &entries)?;
+    Ok(before - entries.len())
+}
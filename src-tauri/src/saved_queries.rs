@@ -0,0 +1,392 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use lakedrop_core::sqlite::quote_ident;
+use polars::prelude::Schema;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+const LIBRARY_VERSION: u32 = 1;
+
+/// How a `{{param}}` value is spliced into the SQL text.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum ParamKind {
+    /// One or more comma-separated column names, checked against the schema
+    /// and quoted as identifiers.
+    Column,
+    /// A string literal.
+    #[default]
+    Text,
+    /// A numeric literal.
+    Number,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct QueryParam {
+    pub(crate) name: String,
+    #[serde(default)]
+    pub(crate) kind: ParamKind,
+    #[serde(default)]
+    pub(crate) default: Option<String>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct SavedQuery {
+    #[serde(default)]
+    pub(crate) id: String,
+    pub(crate) name: String,
+    #[serde(default)]
+    pub(crate) description: String,
+    #[serde(default)]
+    pub(crate) tags: Vec<String>,
+    pub(crate) sql: String,
+    #[serde(default)]
+    pub(crate) params: Vec<QueryParam>,
+}
+
+/// The on-disk and import/export format.
+#[derive(Serialize, Deserialize)]
+struct Library {
+    version: u32,
+    queries: Vec<SavedQuery>,
+}
+
+fn library_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|err: tauri::Error| err.to_string())?;
+    std::fs::create_dir_all(&dir).map_err(|err| err.to_string())?;
+    Ok(dir.join("saved_queries.json"))
+}
+
+fn read_library(path: &Path) -> Result<Vec<SavedQuery>, String> {
+    let text = std::fs::read_to_string(path).map_err(|err| err.to_string())?;
+    let library: Library = serde_json::from_str(&text).map_err(|err| err.to_string())?;
+    if library.version > LIBRARY_VERSION {
+        return Err(format!(
+            "Saved query library version {} is newer than supported ({LIBRARY_VERSION})",
+            library.version
+        ));
+    }
+    Ok(library.queries)
+}
+
+fn write_library(path: &Path, queries: &[SavedQuery]) -> Result<(), String> {
+    let library = Library {
+        version: LIBRARY_VERSION,
+        queries: queries.to_vec(),
+    };
+    let text = serde_json::to_string_pretty(&library).map_err(|err| err.to_string())?;
+    std::fs::write(path, text).map_err(|err| err.to_string())
+}
+
+pub(crate) fn list(app: &AppHandle) -> Result<Vec<SavedQuery>, String> {
+    let path = library_path(app)?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    read_library(&path)
+}
+
+/// Calls `replace` for every `{{name}}` placeholder (inner spaces allowed)
+/// and splices in its result.
+fn substitute(
+    sql: &str,
+    mut replace: impl FnMut(&str) -> Result<String, String>,
+) -> Result<String, String> {
+    let mut output = String::with_capacity(sql.len());
+    let mut rest = sql;
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        output.push_str(&rest[..start]);
+        output.push_str(&replace(rest[start + 2..start + 2 + len].trim())?);
+        rest = &rest[start + 2 + len + 2..];
+    }
+    output.push_str(rest);
+    Ok(output)
+}
+
+/// Names of the `{{param}}` placeholders in `sql`, in order of first use.
+fn placeholders(sql: &str) -> Vec<String> {
+    let mut names = Vec::new();
+    let _ = substitute(sql, |name| {
+        if !name.is_empty() && !names.iter().any(|known| known == name) {
+            names.push(name.to_string());
+        }
+        Ok(String::new())
+    });
+    names
+}
+
+/// Declared params are kept; placeholders without a declaration become text
+/// params, and declarations without a placeholder are dropped.
+fn normalize(mut query: SavedQuery) -> SavedQuery {
+    let names = placeholders(&query.sql);
+    query.params = names
+        .into_iter()
+        .map(|name| {
+            query
+                .params
+                .iter()
+                .find(|param| param.name == name)
+                .cloned()
+                .unwrap_or(QueryParam {
+                    name,
+                    kind: ParamKind::Text,
+                    default: None,
+                })
+        })
+        .collect();
+    query.tags.retain(|tag| !tag.trim().is_empty());
+    query
+}
+
+fn new_id(taken: &[SavedQuery]) -> String {
+    let base = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis())
+        .unwrap_or(0);
+    let mut id = format!("{base:x}");
+    let mut suffix = 1;
+    while taken.iter().any(|query| query.id == id) {
+        id = format!("{base:x}-{suffix}");
+        suffix += 1;
+    }
+    id
+}
+
+fn position(queries: &[SavedQuery], id: &str) -> Option<usize> {
+    if id.is_empty() {
+        return None;
+    }
+    queries.iter().position(|query| query.id == id)
+}
+
+/// Inserts a new query or replaces the one with the same id.
+pub(crate) fn save(app: &AppHandle, query: SavedQuery) -> Result<SavedQuery, String> {
+    if query.name.trim().is_empty() {
+        return Err("Saved query needs a name".to_string());
+    }
+    let mut queries = list(app)?;
+    let mut query = normalize(query);
+    match position(&queries, &query.id) {
+        Some(idx) => queries[idx] = query.clone(),
+        None => {
+            query.id = new_id(&queries);
+            queries.push(query.clone());
+        }
+    }
+    write_library(&library_path(app)?, &queries)?;
+    Ok(query)
+}
+
+pub(crate) fn delete(app: &AppHandle, id: &str) -> Result<(), String> {
+    let mut queries = list(app)?;
+    queries.retain(|query| query.id != id);
+    write_library(&library_path(app)?, &queries)
+}
+
+pub(crate) fn export(app: &AppHandle, path: &Path) -> Result<usize, String> {
+    let queries = list(app)?;
+    write_library(path, &queries)?;
+    Ok(queries.len())
+}
+
+/// Merges a shared library into the local one. Queries with a known id are
+/// replaced; the rest are added.
+pub(crate) fn import(app: &AppHandle, path: &Path) -> Result<usize, String> {
+    let incoming = read_library(path)?;
+    let mut queries = list(app)?;
+    let count = incoming.len();
+    for query in incoming {
+        let mut query = normalize(query);
+        match position(&queries, &query.id) {
+            Some(idx) => queries[idx] = query,
+            None => {
+                if query.id.is_empty() {
+                    query.id = new_id(&queries);
+                }
+                queries.push(query);
+            }
+        }
+    }
+    write_library(&library_path(app)?, &queries)?;
+    Ok(count)
+}
+
+fn render_value(param: &QueryParam, value: &str, schema: &Schema) -> Result<String, String> {
+    match param.kind {
+        ParamKind::Column => {
+            let columns = value
+                .split(',')
+                .map(str::trim)
+                .filter(|column| !column.is_empty())
+                .collect::<Vec<_>>();
+            if columns.is_empty() {
+                return Err(format!("Parameter {} needs a column name", param.name));
+            }
+            columns
+                .iter()
+                .map(|column| {
+                    if schema.contains(column) {
+                        Ok(quote_ident(column))
+                    } else {
+                        Err(format!(
+                            "Parameter {}: column {column} is not in the current schema",
+                            param.name
+                        ))
+                    }
+                })
+                .collect::<Result<Vec<_>, _>>()
+                .map(|columns| columns.join(", "))
+        }
+        // `inf`, `NaN` and out-of-range values like `1e999` parse as f64 but
+        // are not SQL numbers.
+        ParamKind::Number => value
+            .trim()
+            .parse::<f64>()
+            .ok()
+            .filter(|number| number.is_finite())
+            .map(|_| value.trim().to_string())
+            .ok_or_else(|| format!("Parameter {} must be a number", param.name)),
+        ParamKind::Text => Ok(format!("'{}'", value.replace('\'', "''"))),
+    }
+}
+
+/// Substitutes every placeholder, falling back to each param's default, and
+/// checks column params against `schema`.
+pub(crate) fn render(
+    query: &SavedQuery,
+    values: &HashMap<String, String>,
+    schema: &Schema,
+) -> Result<String, String> {
+    let query = normalize(query.clone());
+    substitute(&query.sql, |name| {
+        let param = query
+            .params
+            .iter()
+            .find(|param| param.name == name)
+            .ok_or_else(|| format!("Unknown parameter {name}"))?;
+        let value = values
+            .get(name)
+            .or(param.default.as_ref())
+            .ok_or_else(|| format!("Missing value for parameter {name}"))?;
+        render_value(param, value, schema)
+    })
+}
+
+pub(crate) fn find(app: &AppHandle, id: &str) -> Result<SavedQuery, String> {
+    list(app)?
+        .into_iter()
+        .find(|query| query.id == id)
+        .ok_or_else(|| format!("Saved query not found: {id}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use polars::prelude::{DataType, Field};
+
+    use super::*;
+
+    fn schema() -> Schema {
+        Schema::from_iter([
+            Field::new("amount", DataType::Float64),
+            Field::new("customer name", DataType::String),
+        ])
+    }
+
+    fn query(sql: &str, params: Vec<QueryParam>) -> SavedQuery {
+        SavedQuery {
+            id: String::new(),
+            name: "test".to_string(),
+            description: String::new(),
+            tags: Vec::new(),
+            sql: sql.to_string(),
+            params,
+        }
+    }
+
+    fn param(name: &str, kind: ParamKind, default: Option<&str>) -> QueryParam {
+        QueryParam {
+            name: name.to_string(),
+            kind,
+            default: default.map(str::to_string),
+        }
+    }
+
+    fn values(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn substitute_replaces_each_placeholder() {
+        let sql = substitute("a {{ x }} b {{y}} {{x}} {{open", |name| {
+            Ok(name.to_uppercase())
+        })
+        .unwrap();
+        assert_eq!(sql, "a X b Y X {{open");
+        assert_eq!(placeholders("{{b}} {{a}} {{ b }} {{}}"), ["b", "a"]);
+    }
+
+    #[test]
+    fn render_quotes_each_kind() {
+        let saved = query(
+            "SELECT {{cols}} FROM source WHERE amount > {{min}} AND \"customer name\" = {{who}}",
+            vec![
+                param("cols", ParamKind::Column, None),
+                param("min", ParamKind::Number, Some("0")),
+                param("who", ParamKind::Text, None),
+            ],
+        );
+        let sql = render(
+            &saved,
+            &values(&[("cols", "amount, customer name"), ("who", "O'Brien")]),
+            &schema(),
+        )
+        .unwrap();
+        assert_eq!(
+            sql,
+            "SELECT \"amount\", \"customer name\" FROM source \
+             WHERE amount > 0 AND \"customer name\" = 'O''Brien'"
+        );
+    }
+
+    #[test]
+    fn render_treats_undeclared_placeholders_as_text() {
+        let saved = query("SELECT * FROM source WHERE region = {{region}}", Vec::new());
+        let sql = render(&saved, &values(&[("region", "north")]), &schema()).unwrap();
+        assert_eq!(sql, "SELECT * FROM source WHERE region = 'north'");
+    }
+
+    #[test]
+    fn render_rejects_bad_values() {
+        let saved = query(
+            "SELECT {{col}} FROM source LIMIT {{n}}",
+            vec![
+                param("col", ParamKind::Column, Some("amount")),
+                param("n", ParamKind::Number, None),
+            ],
+        );
+        let schema = schema();
+        assert!(render(&saved, &values(&[]), &schema).is_err());
+        for number in ["ten", "inf", "-Infinity", "NaN", "1e999"] {
+            assert!(
+                render(&saved, &values(&[("n", number)]), &schema).is_err(),
+                "{number}"
+            );
+        }
+        assert!(render(&saved, &values(&[("col", "amont"), ("n", "10")]), &schema).is_err());
+        assert_eq!(
+            render(&saved, &values(&[("n", "10")]), &schema).unwrap(),
+            "SELECT \"amount\" FROM source LIMIT 10"
+        );
+    }
+}
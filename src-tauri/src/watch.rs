@@ -0,0 +1,66 @@
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+pub(crate) const DEFAULT_INTERVAL: Duration = Duration::from_millis(1000);
+
+/// Polls a file's size and modification time on a background thread. Polling
+/// rather than OS notifications keeps this working on network shares, where
+/// log files written by remote jobs often live. Dropping the watcher stops
+/// the thread at its next tick.
+pub(crate) struct FileWatcher {
+    path: PathBuf,
+    stop: Arc<AtomicBool>,
+}
+
+impl FileWatcher {
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for FileWatcher {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+fn stamp(path: &Path) -> Option<(u64, SystemTime)> {
+    let meta = std::fs::metadata(path).ok()?;
+    Some((meta.len(), meta.modified().ok()?))
+}
+
+/// Calls `on_change` whenever `path` has a new size or mtime. A file that is
+/// briefly missing (rotated or rewritten) is not reported until it is back.
+/// `on_change` returns `false` to stop watching.
+pub(crate) fn watch(
+    path: PathBuf,
+    interval: Duration,
+    mut on_change: impl FnMut() -> bool + Send + 'static,
+) -> FileWatcher {
+    let stop = Arc::new(AtomicBool::new(false));
+    let watcher = FileWatcher {
+        path: path.clone(),
+        stop: stop.clone(),
+    };
+    thread::spawn(move || {
+        let mut last = stamp(&path);
+        loop {
+            thread::sleep(interval);
+            if stop.load(Ordering::Relaxed) {
+                return;
+            }
+            let current = stamp(&path);
+            if current.is_none() || current == last {
+                continue;
+            }
+            last = current;
+            if !on_change() {
+                return;
+            }
+        }
+    });
+    watcher
+}
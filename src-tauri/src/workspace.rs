@@ -0,0 +1,141 @@
+use std::path::{Path, PathBuf};
+
+use lakedrop_core::fixed_width::FixedWidthOptions;
+use lakedrop_core::s3::{is_s3_uri, S3Settings};
+use lakedrop_core::{http, FileKind};
+use serde::{Deserialize, Serialize};
+
+pub(crate) const WORKSPACE_VERSION: u32 = 1;
+pub(crate) const WORKSPACE_SUFFIX: &str = ".lakedrop.json";
+
+/// A saved session: the open source with its selections, connection settings
+/// and whatever editor state the frontend hands over.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct Workspace {
+    pub(crate) version: u32,
+    #[serde(default)]
+    pub(crate) source: Option<WorkspaceSource>,
+    #[serde(default)]
+    pub(crate) s3: S3Settings,
+    /// Editor tabs and export settings, stored exactly as the frontend sends
+    /// them.
+    #[serde(default)]
+    pub(crate) editor: serde_json::Value,
+}
+
+#[derive(Serialize, Deserialize)]
+pub(crate) struct WorkspaceSource {
+    /// Local path or `s3://` / `http(s)://` URI, as originally opened.
+    pub(crate) path: String,
+    /// The same file relative to the workspace, tried when `path` is gone so
+    /// a project folder can be moved or shared.
+    #[serde(default)]
+    pub(crate) relative_path: Option<String>,
+    pub(crate) kind: FileKind,
+    #[serde(default)]
+    pub(crate) active_sheet: Option<String>,
+    #[serde(default)]
+    pub(crate) active_version: Option<String>,
+    #[serde(default)]
+    pub(crate) fixed_width: Option<FixedWidthOptions>,
+    /// Extra SQL tables registered next to `source`.
+    #[serde(default)]
+    pub(crate) tables: Vec<String>,
+}
+
+fn is_remote(path: &str) -> bool {
+    is_s3_uri(path) || http::is_http_url(path)
+}
+
+fn has_suffix(path: &Path) -> bool {
+    path.to_string_lossy()
+        .to_lowercase()
+        .ends_with(WORKSPACE_SUFFIX)
+}
+
+/// `path` ending in `.lakedrop.json`: a plain `.json` extension is replaced
+/// and anything else gets the suffix appended.
+pub(crate) fn with_suffix(path: &Path) -> PathBuf {
+    if has_suffix(path) {
+        return path.to_path_buf();
+    }
+    let text = path.to_string_lossy();
+    let stem = match text.len().checked_sub(".json".len()) {
+        Some(cut) if text.is_char_boundary(cut) && text[cut..].eq_ignore_ascii_case(".json") => {
+            &text[..cut]
+        }
+        _ => &text[..],
+    };
+    PathBuf::from(format!("{stem}{WORKSPACE_SUFFIX}"))
+}
+
+pub(crate) fn read(path: &Path) -> Result<Workspace, String> {
+    if !has_suffix(path) {
+        return Err(format!(
+            "Not a LakeDrop workspace: {} (expected a {WORKSPACE_SUFFIX} file)",
+            path.display()
+        ));
+    }
+    let text = std::fs::read_to_string(path).map_err(|err| err.to_string())?;
+    let workspace: Workspace = serde_json::from_str(&text).map_err(|err| err.to_string())?;
+    if workspace.version > WORKSPACE_VERSION {
+        return Err(format!(
+            "Workspace version {} is newer than supported ({WORKSPACE_VERSION})",
+            workspace.version
+        ));
+    }
+    Ok(workspace)
+}
+
+pub(crate) fn write(path: &Path, workspace: &Workspace) -> Result<(), String> {
+    let text = serde_json::to_string_pretty(workspace).map_err(|err| err.to_string())?;
+    std::fs::write(path, text).map_err(|err| err.to_string())
+}
+
+/// `file` relative to the workspace's directory, when it lives below it.
+pub(crate) fn relative_to(workspace_path: &Path, file: &str) -> Option<String> {
+    if is_remote(file) {
+        return None;
+    }
+    let dir = workspace_path.parent()?;
+    Path::new(file)
+        .strip_prefix(dir)
+        .ok()
+        .map(|relative| relative.to_string_lossy().replace('\\', "/"))
+}
+
+/// The path to reopen, or `None` when the file is gone from both its
+/// original and its workspace-relative location. Remote URIs are returned
+/// as-is and checked when they are scanned.
+pub(crate) fn resolve(workspace_path: &Path, source: &WorkspaceSource) -> Option<String> {
+    if is_remote(&source.path) || Path::new(&source.path).exists() {
+        return Some(source.path.clone());
+    }
+    let dir = workspace_path.parent()?;
+    source
+        .relative_path
+        .as_ref()
+        .map(|relative| dir.join(relative))
+        .filter(|path| path.exists())
+        .map(|path| path.display().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn workspace_paths_end_in_the_suffix() {
+        let suffixed = |path: &str| with_suffix(Path::new(path)).display().to_string();
+        assert_eq!(suffixed("/p/sales.lakedrop.json"), "/p/sales.lakedrop.json");
+        assert_eq!(suffixed("/p/Sales.LakeDrop.JSON"), "/p/Sales.LakeDrop.JSON");
+        assert_eq!(suffixed("/p/sales.json"), "/p/sales.lakedrop.json");
+        assert_eq!(suffixed("/p/sales"), "/p/sales.lakedrop.json");
+    }
+
+    #[test]
+    fn read_rejects_other_files() {
+        let err = read(Path::new("/p/package.json")).err().unwrap();
+        assert!(err.contains(WORKSPACE_SUFFIX), "{err}");
+    }
+}
@@ -3,7 +3,7 @@ import { invoke } from "@tauri-apps/api/core";
 import { listen } from "@tauri-apps/api/event";
 import { open, save } from "@tauri-apps/plugin-dialog";
 import { useTranslation } from "react-i18next";
-import Editor from "@monaco-editor/react";
+import Editor, { type OnMount } from "@monaco-editor/react";
 import { useVirtualizer } from "@tanstack/react-virtual";
 import clsx from "clsx";
 import toast, { Toaster } from "react-hot-toast";
@@ -14,14 +14,58 @@ type FieldInfo = {
   dtype: string;
 };
 
+type BlockInfo = {
+  row_count: number;
+  byte_size: number;
+};
+
+type StorageInfo = {
+  format: string;
+  block_kind: "row_group" | "stripe";
+  compression: string[];
+  blocks: BlockInfo[];
+};
+
+type TableVersion = {
+  id: string;
+  timestamp_ms?: number | null;
+  operation?: string | null;
+};
+
+type FixedWidthColumn = {
+  name: string;
+  start: number;
+  width: number;
+  dtype?: string | null;
+};
+
+type FixedWidthOptions = {
+  columns: FixedWidthColumn[];
+  skip_rows: number;
+};
+
+type WorkbookRange = {
+  name: string;
+  kind: "table" | "name";
+  sheet: string;
+};
+
 type FileMetadataResponse = {
   file_name: string;
   file_path: string;
   file_size: number;
+  file_kind: string;
   row_count: number;
   schema: FieldInfo[];
   sheets: string[];
   active_sheet?: string | null;
+  ranges: WorkbookRange[];
+  tables: string[];
+  storage?: StorageInfo | null;
+  versions: TableVersion[];
+  active_version?: string | null;
+  fixed_width?: FixedWidthOptions | null;
+  schema_changed: boolean;
 };
 
 type ColumnInfo = {
@@ -33,6 +77,124 @@ type QueryResult = {
   columns: ColumnInfo[];
   rows: unknown[][];
   row_count: number;
+  total_rows: number;
+};
+
+type DownloadProgress = {
+  url: string;
+  downloaded: number;
+  total?: number | null;
+};
+
+type SourceChanged = {
+  file_path: string;
+  file_size: number;
+  row_count?: number | null;
+  error?: string | null;
+};
+
+type HistoryEntry = {
+  id: number;
+  sql: string;
+  files: string[];
+  executed_at_ms: number;
+  duration_ms: number;
+  row_count?: number | null;
+  error?: string | null;
+  pinned: boolean;
+};
+
+type RecentFile = {
+  path: string;
+  kind: string;
+  size: number;
+  modified_ms?: number | null;
+  last_opened_ms: number;
+  pinned: boolean;
+  missing: boolean;
+};
+
+type S3Settings = {
+  endpoint?: string | null;
+  region?: string | null;
+  profile?: string | null;
+  allow_http: boolean;
+};
+
+type ApiStatus = {
+  running: boolean;
+  url?: string | null;
+  token?: string | null;
+};
+
+type ApiQuery = {
+  id: number;
+  sql: string;
+  format: string;
+  client?: string | null;
+  started_ms: number;
+  duration_ms: number;
+  row_count?: number | null;
+  error?: string | null;
+};
+
+// start/end are UTF-16 offsets into the query; line/column are 1-based and
+// count characters.
+type SqlSpan = {
+  start: number;
+  end: number;
+  line: number;
+  column: number;
+};
+
+// Error returned by the loading, query and export commands.
+type AppError = {
+  code: string;
+  message: string;
+  span?: SqlSpan | null;
+  path?: string | null;
+  hint?: string | null;
+  identifier?: string | null;
+  suggestions?: string[];
+};
+
+type Completion = {
+  label: string;
+  kind: "table" | "column" | "function" | "keyword";
+  detail?: string | null;
+  insert_text: string;
+};
+
+type QueryParam = {
+  name: string;
+  kind: "column" | "text" | "number";
+  default?: string | null;
+};
+
+type SavedQuery = {
+  id: string;
+  name: string;
+  description: string;
+  tags: string[];
+  sql: string;
+  params: QueryParam[];
+};
+
+type EditorTab = {
+  title: string;
+  sql: string;
+};
+
+type EditorState = {
+  tabs: EditorTab[];
+  active: number;
+};
+
+type WorkspaceRestore = {
+  file?: FileMetadataResponse | null;
+  editor?: EditorState | null;
+  missing: string[];
+  warnings: string[];
 };
 
 type ContextMenuState = {
@@ -43,6 +205,18 @@ type ContextMenuState = {
 };
 
 const DEFAULT_SQL = "SELECT * FROM source LIMIT 3";
+const WATCHABLE_KINDS = [
+  "csv",
+  "json_lines",
+  "json",
+  "parquet",
+  "arrow",
+  "avro",
+  "orc",
+  "fixed_width",
+];
+// "off", "watch" (reload only) or a tail interval in milliseconds.
+const LIVE_MODES = ["off", "watch", "2000", "5000", "10000"];
 
 function formatBytes(bytes: number) {
   if (!bytes) return "0 B";
@@ -63,8 +237,30 @@ function compareValues(a: unknown, b: unknown) {
   return String(a).localeCompare(String(b), undefined, { numeric: true });
 }
 
+function isAppError(error: unknown): error is AppError {
+  return typeof error === "object" && error !== null && "code" in error && "message" in error;
+}
+
 function App() {
   const { t, i18n } = useTranslation();
+  // Localized by code ("file_not_found" -> "errorFileNotFound"), falling
+  // back to the backend's English message.
+  const errorText = (error: unknown) => {
+    if (!isAppError(error)) return String(error);
+    const name = error.code.replace(/(^|_)(\w)/g, (_, __, ch: string) => ch.toUpperCase());
+    const key = `error${name}`;
+    const text = i18n.exists(key)
+      ? t(key, {
+          message: error.message,
+          path: error.path ?? "",
+          identifier: error.identifier ?? "",
+        })
+      : error.message;
+    const hint = error.suggestions?.length
+      ? t("errorDidYouMean", { names: error.suggestions.join(", ") })
+      : error.hint;
+    return hint ? `${text}\n${hint}` : text;
+  };
   const [fileMeta, setFileMeta] = useState<FileMetadataResponse | null>(null);
   const [sql, setSql] = useState(DEFAULT_SQL);
   const [result, setResult] = useState<QueryResult | null>(null);
@@ -74,7 +270,17 @@ function App() {
   const [queryMs, setQueryMs] = useState<number | null>(null);
   const [theme, setTheme] = useState<"light" | "dark">("light");
   const [menuState, setMenuState] = useState<ContextMenuState | null>(null);
+  const [history, setHistory] = useState<HistoryEntry[]>([]);
+  const [historySearch, setHistorySearch] = useState("");
+  const [savedQueries, setSavedQueries] = useState<SavedQuery[]>([]);
+  const [recentFiles, setRecentFiles] = useState<RecentFile[]>([]);
+  const [liveMode, setLiveMode] = useState("off");
+  const [apiStatus, setApiStatus] = useState<ApiStatus>({ running: false });
+  const [apiQueries, setApiQueries] = useState<ApiQuery[]>([]);
   const tableParentRef = useRef<HTMLDivElement>(null);
+  const editorRef = useRef<Parameters<OnMount>[0] | null>(null);
+  const monacoRef = useRef<Parameters<OnMount>[1] | null>(null);
+  const completionRef = useRef<{ dispose: () => void } | null>(null);
   const resizingRef = useRef<{
     index: number;
     startX: number;
@@ -116,6 +322,8 @@ function App() {
   const virtualRows = rowVirtualizer.getVirtualItems();
   const totalSize = rowVirtualizer.getTotalSize();
 
+  useEffect(() => () => completionRef.current?.dispose(), []);
+
   useEffect(() => {
     const storedTheme = window.localStorage.getItem("lakedrop-theme");
     const storedLanguage = window.localStorage.getItem("lakedrop-lang");
@@ -138,6 +346,9 @@ function App() {
     let unlistenDrop: (() => void) | null = null;
     let unlistenHover: (() => void) | null = null;
     let unlistenCancel: (() => void) | null = null;
+    let unlistenDownload: (() => void) | null = null;
+    let unlistenSource: (() => void) | null = null;
+    let unlistenApi: (() => void) | null = null;
 
     listen<string[]>("tauri://file-drop", (event) => {
       const [path] = event.payload ?? [];
@@ -161,13 +372,69 @@ function App() {
       },
     );
 
+    listen<DownloadProgress>("download-progress", (event) => {
+      const { downloaded, total } = event.payload;
+      if (total !== null && total !== undefined && downloaded >= total) {
+        toast.dismiss("download");
+        return;
+      }
+      const progress = total
+        ? `${Math.round((downloaded / total) * 100)}%`
+        : formatBytes(downloaded);
+      toast.loading(t("downloading", { progress }), { id: "download" });
+    }).then((unlisten) => {
+      unlistenDownload = unlisten;
+    });
+
+    listen<SourceChanged>("source-changed", (event) => {
+      const { file_path, file_size, row_count, error } = event.payload;
+      if (error) {
+        toast.error(t("reloadFailed", { error }), { id: "source-changed" });
+        return;
+      }
+      setFileMeta((prev) =>
+        prev && prev.file_path === file_path
+          ? { ...prev, file_size, row_count: row_count ?? prev.row_count }
+          : prev,
+      );
+    }).then((unlisten) => {
+      unlistenSource = unlisten;
+    });
+
+    listen<ApiQuery>("api-query", (event) => {
+      setApiQueries((prev) => [event.payload, ...prev].slice(0, 200));
+    }).then((unlisten) => {
+      unlistenApi = unlisten;
+    });
+
     return () => {
       unlistenDrop?.();
       unlistenHover?.();
       unlistenCancel?.();
+      unlistenDownload?.();
+      unlistenSource?.();
+      unlistenApi?.();
     };
   }, []);
 
+  useEffect(() => {
+    const timer = window.setTimeout(() => refreshHistory(historySearch), 200);
+    return () => window.clearTimeout(timer);
+  }, [historySearch]);
+
+  const tailMs = Number(liveMode) || 0;
+  useEffect(() => {
+    if (!tailMs || !fileMeta) return;
+    const timer = window.setInterval(() => tailQuery(), tailMs);
+    return () => window.clearInterval(timer);
+  }, [tailMs, sql, fileMeta?.file_path]);
+
+  useEffect(() => {
+    refreshSavedQueries();
+    refreshRecentFiles();
+    refreshApiServer();
+  }, []);
+
   useEffect(() => {
     const closeMenu = () => setMenuState(null);
     window.addEventListener("click", closeMenu);
@@ -209,25 +476,175 @@ function App() {
   }, []);
 
   async function loadFile(path: string) {
+    if (path.toLowerCase().endsWith(".lakedrop.json")) {
+      await openWorkspace(path);
+      return;
+    }
     setIsLoadingFile(true);
     try {
       const response = await invoke<FileMetadataResponse>("scan_file_metadata", {
         path,
       });
       setFileMeta(response);
+      setLiveMode("off");
       setSql(DEFAULT_SQL);
       setResult(null);
       setQueryMs(null);
       setSortState(null);
       toast.success(t("fileLoaded"));
+      if (response.schema_changed) {
+        toast.error(t("schemaChanged"));
+      }
       await runQuery(DEFAULT_SQL);
     } catch (error) {
-      toast.error(String(error));
+      toast.error(errorText(error));
+    } finally {
+      setIsLoadingFile(false);
+      refreshRecentFiles();
+    }
+  }
+
+  async function refreshRecentFiles() {
+    try {
+      setRecentFiles(await invoke<RecentFile[]>("list_recent_files"));
+    } catch (error) {
+      console.error(error);
+    }
+  }
+
+  async function pinRecentFile(file: RecentFile) {
+    try {
+      await invoke("pin_recent_file", { path: file.path, pinned: !file.pinned });
+      await refreshRecentFiles();
+    } catch (error) {
+      toast.error(errorText(error));
+    }
+  }
+
+  async function pruneRecentFiles() {
+    try {
+      const count = await invoke<number>("prune_recent_files", {});
+      toast.success(t("recentPruned", { count }));
+      await refreshRecentFiles();
+    } catch (error) {
+      toast.error(errorText(error));
+    }
+  }
+
+  async function refreshApiServer() {
+    try {
+      setApiStatus(await invoke<ApiStatus>("api_server_status"));
+      setApiQueries(await invoke<ApiQuery[]>("list_api_queries"));
+    } catch (error) {
+      console.error(error);
+    }
+  }
+
+  async function toggleApiServer() {
+    try {
+      if (apiStatus.running) {
+        await invoke("stop_api_server");
+        setApiStatus({ running: false });
+      } else {
+        setApiStatus(await invoke<ApiStatus>("start_api_server", {}));
+        toast.success(t("apiStarted"));
+      }
+    } catch (error) {
+      toast.error(errorText(error));
+    }
+  }
+
+  function copyApiToken() {
+    navigator.clipboard
+      .writeText(apiStatus.token ?? "")
+      .then(() => toast.success(t("copied")))
+      .catch(() => toast.error(t("copyFailed")));
+  }
+
+  async function saveWorkspace() {
+    const path = await save({
+      title: t("saveWorkspace"),
+      defaultPath: `${fileMeta?.file_name.replace(/\.[^.]+$/, "") ?? "workspace"}.lakedrop.json`,
+      filters: [{ name: "LakeDrop Workspace", extensions: ["json"] }],
+    });
+    if (!path) return;
+    const editor: EditorState = { tabs: [{ title: "Query 1", sql }], active: 0 };
+    try {
+      const saved = await invoke<string>("save_workspace", { path, editor });
+      toast.success(t("workspaceSaved", { path: saved }));
+    } catch (error) {
+      toast.error(errorText(error));
+    }
+  }
+
+  async function pickWorkspace() {
+    const path = await open({
+      multiple: false,
+      filters: [{ name: "LakeDrop Workspace", extensions: ["json"] }],
+    });
+    if (typeof path === "string") {
+      await openWorkspace(path);
+    }
+  }
+
+  async function openWorkspace(path: string) {
+    setIsLoadingFile(true);
+    try {
+      const restore = await invoke<WorkspaceRestore>("open_workspace", { path });
+      const tab = restore.editor?.tabs?.[restore.editor.active ?? 0];
+      const nextSql = tab?.sql ?? DEFAULT_SQL;
+      setFileMeta(restore.file ?? null);
+      setLiveMode("off");
+      setSql(nextSql);
+      setResult(null);
+      setQueryMs(null);
+      setSortState(null);
+      if (restore.missing.length) {
+        toast.error(t("workspaceMissing", { files: restore.missing.join(", ") }));
+      }
+      restore.warnings.forEach((warning) => toast.error(warning));
+      if (restore.file?.schema_changed) {
+        toast.error(t("schemaChanged"));
+      }
+      if (restore.file) {
+        toast.success(t("workspaceOpened"));
+        await runQuery(nextSql);
+      }
+    } catch (error) {
+      toast.error(errorText(error));
     } finally {
       setIsLoadingFile(false);
+      refreshRecentFiles();
     }
   }
 
+  // Underlines the part of the editor query an error points at; clears the
+  // underline when there is no position.
+  function markSqlError(error: unknown) {
+    const model = editorRef.current?.getModel();
+    const monaco = monacoRef.current;
+    if (!model || !monaco) return;
+    const span = isAppError(error) ? error.span : null;
+    const start = span ? model.getPositionAt(span.start) : null;
+    const end = span ? model.getPositionAt(Math.max(span.end, span.start + 1)) : null;
+    monaco.editor.setModelMarkers(
+      model,
+      "lakedrop",
+      start && end
+        ? [
+            {
+              startLineNumber: start.lineNumber,
+              startColumn: start.column,
+              endLineNumber: end.lineNumber,
+              endColumn: end.column,
+              message: errorText(error),
+              severity: monaco.MarkerSeverity.Error,
+            },
+          ]
+        : [],
+    );
+  }
+
   async function runQuery(overrideSql?: string) {
     const queryText = overrideSql ?? sql;
     if (!queryText.trim()) {
@@ -244,10 +661,187 @@ function App() {
       setResult(response);
       setQueryMs(Math.round(performance.now() - start));
       setSortState(null);
+      markSqlError(null);
     } catch (error) {
-      toast.error(String(error));
+      if (overrideSql === undefined) markSqlError(error);
+      toast.error(errorText(error));
     } finally {
       setIsRunningQuery(false);
+      refreshHistory(historySearch);
+    }
+  }
+
+  async function formatQuery(text = sql) {
+    try {
+      const formatted = await invoke<string>("format_sql", {
+        sql: text,
+        options: { keyword_case: "upper", indent: 2 },
+      });
+      setSql(formatted);
+      markSqlError(null);
+    } catch (error) {
+      markSqlError(error);
+      toast.error(errorText(error));
+    }
+  }
+
+  async function tailQuery() {
+    try {
+      const response = await invoke<QueryResult>("tail_sql", {
+        sql,
+        maxRows: 1000,
+      });
+      setResult(response);
+    } catch (error) {
+      setLiveMode("watch");
+      toast.error(errorText(error));
+    }
+  }
+
+  async function changeLiveMode(mode: string) {
+    try {
+      await invoke("watch_file", { enabled: mode !== "off" });
+      setLiveMode(mode);
+    } catch (error) {
+      toast.error(errorText(error));
+    }
+  }
+
+  async function refreshHistory(search: string) {
+    try {
+      const entries = await invoke<HistoryEntry[]>("list_query_history", {
+        search: search.trim() || null,
+      });
+      setHistory(entries);
+    } catch (error) {
+      console.error(error);
+    }
+  }
+
+  async function refreshSavedQueries() {
+    try {
+      setSavedQueries(await invoke<SavedQuery[]>("list_saved_queries"));
+    } catch (error) {
+      console.error(error);
+    }
+  }
+
+  async function saveCurrentQuery() {
+    const name = window.prompt(t("savedName"));
+    if (!name?.trim()) return;
+    const description = window.prompt(t("savedDescription")) ?? "";
+    const tags = (window.prompt(t("savedTags")) ?? "")
+      .split(",")
+      .map((tag) => tag.trim())
+      .filter(Boolean);
+    const names = [...new Set([...sql.matchAll(/\{\{\s*([^}]+?)\s*\}\}/g)].map((m) => m[1]))];
+    const params: QueryParam[] = [];
+    for (const param of names) {
+      // "column", "number=10" or "text=abc"; kind defaults to text.
+      const spec = window.prompt(t("savedParamPrompt", { name: param }), "text");
+      if (spec === null) return;
+      const [kindText, ...rest] = spec.split("=");
+      const kind = kindText.trim();
+      params.push({
+        name: param,
+        kind: kind === "column" || kind === "number" ? kind : "text",
+        default: rest.length ? rest.join("=") : null,
+      });
+    }
+    try {
+      await invoke("save_query", {
+        query: { id: "", name: name.trim(), description, tags, sql, params },
+      });
+      await refreshSavedQueries();
+      toast.success(t("savedQuerySaved"));
+    } catch (error) {
+      toast.error(errorText(error));
+    }
+  }
+
+  async function runSavedQuery(query: SavedQuery) {
+    const params: Record<string, string> = {};
+    for (const param of query.params) {
+      const value = window.prompt(
+        t("savedParamValue", { name: param.name, kind: param.kind }),
+        param.default ?? "",
+      );
+      if (value === null) return;
+      params[param.name] = value;
+    }
+    setIsRunningQuery(true);
+    const start = performance.now();
+    try {
+      const response = await invoke<QueryResult>("run_saved_query", {
+        id: query.id,
+        params,
+        maxRows: 1000,
+      });
+      setResult(response);
+      setQueryMs(Math.round(performance.now() - start));
+      setSortState(null);
+    } catch (error) {
+      toast.error(errorText(error));
+    } finally {
+      setIsRunningQuery(false);
+      refreshHistory(historySearch);
+    }
+  }
+
+  async function deleteSavedQuery(query: SavedQuery) {
+    try {
+      await invoke("delete_saved_query", { id: query.id });
+      await refreshSavedQueries();
+    } catch (error) {
+      toast.error(errorText(error));
+    }
+  }
+
+  async function exportSavedQueries() {
+    const path = await save({
+      title: t("savedExport"),
+      defaultPath: "lakedrop-queries.json",
+      filters: [{ name: "JSON", extensions: ["json"] }],
+    });
+    if (!path) return;
+    try {
+      const count = await invoke<number>("export_saved_queries", { path });
+      toast.success(t("savedExported", { count }));
+    } catch (error) {
+      toast.error(errorText(error));
+    }
+  }
+
+  async function importSavedQueries() {
+    const path = await open({
+      multiple: false,
+      filters: [{ name: "JSON", extensions: ["json"] }],
+    });
+    if (typeof path !== "string") return;
+    try {
+      const count = await invoke<number>("import_saved_queries", { path });
+      await refreshSavedQueries();
+      toast.success(t("savedImported", { count }));
+    } catch (error) {
+      toast.error(errorText(error));
+    }
+  }
+
+  async function pinHistoryEntry(entry: HistoryEntry) {
+    try {
+      await invoke("pin_query_history", { id: entry.id, pinned: !entry.pinned });
+      await refreshHistory(historySearch);
+    } catch (error) {
+      toast.error(errorText(error));
+    }
+  }
+
+  async function deleteHistoryEntry(entry: HistoryEntry) {
+    try {
+      await invoke("delete_query_history", { id: entry.id });
+      await refreshHistory(historySearch);
+    } catch (error) {
+      toast.error(errorText(error));
     }
   }
 
@@ -263,30 +857,130 @@ function App() {
       filters: [{ name: format.toUpperCase(), extensions: [format] }],
     });
     if (!path) return;
+    try {
+      await invoke("export_query", { sql, path, options: { format } });
+      toast.success(t("exportSuccess"));
+    } catch (error) {
+      toast.error(errorText(error));
+    }
+  }
+
+  async function exportSqlite() {
+    if (!fileMeta) {
+      toast.error(t("noFile"));
+      return;
+    }
+    const path = await save({
+      title: t("exportTitle"),
+      defaultPath: `${fileMeta.file_name.replace(/\.[^.]+$/, "")}.db`,
+      filters: [{ name: "SQLite", extensions: ["db", "sqlite", "sqlite3"] }],
+    });
+    if (!path) return;
+    const tableName = window.prompt(t("exportTablePrompt"), "source");
+    if (!tableName?.trim()) return;
+    const append = window.confirm(t("exportAppendTableConfirm"));
     try {
       await invoke("export_query", {
         sql,
         path,
-        format,
+        options: {
+          format: "sqlite",
+          table_name: tableName.trim(),
+          if_exists: append ? "append" : "replace",
+        },
+      });
+      toast.success(t("exportSuccess"));
+    } catch (error) {
+      toast.error(errorText(error));
+    }
+  }
+
+  async function exportPartitioned() {
+    if (!fileMeta) {
+      toast.error(t("noFile"));
+      return;
+    }
+    const partitionBy = (window.prompt(t("exportPartitionPrompt")) ?? "")
+      .split(",")
+      .map((column) => column.trim())
+      .filter(Boolean);
+    if (!partitionBy.length) return;
+    const path = await open({ title: t("exportTitle"), directory: true });
+    if (typeof path !== "string") return;
+    const append = window.confirm(t("exportAppendConfirm"));
+    try {
+      await invoke("export_query", {
+        sql,
+        path,
+        options: {
+          format: "parquet",
+          partition_by: partitionBy,
+          write_manifest: true,
+          if_exists: append ? "append" : "replace",
+        },
       });
       toast.success(t("exportSuccess"));
     } catch (error) {
-      toast.error(String(error));
+      toast.error(errorText(error));
     }
   }
 
   async function selectSheet(sheet: string) {
     if (!sheet) return;
     try {
-      const response = await invoke<FileMetadataResponse>("select_excel_sheet", {
-        sheet,
+      const response =
+        fileMeta?.file_kind === "sqlite"
+          ? await invoke<FileMetadataResponse>("select_sqlite_table", {
+              table: sheet,
+            })
+          : fileMeta?.file_kind === "archive"
+            ? await invoke<FileMetadataResponse>("select_archive_member", {
+                member: sheet,
+              })
+            : await invoke<FileMetadataResponse>("select_excel_sheet", {
+                sheet,
+              });
+      setFileMeta(response);
+      setResult(null);
+      setQueryMs(null);
+      setSortState(null);
+    } catch (error) {
+      toast.error(errorText(error));
+    }
+  }
+
+  async function registerArchiveMembers() {
+    try {
+      const tables = await invoke<string[]>("register_archive_members");
+      setFileMeta((current) => (current ? { ...current, tables } : current));
+      toast.success(t("tablesRegistered", { count: tables.length }));
+    } catch (error) {
+      toast.error(errorText(error));
+    }
+  }
+
+  async function registerExcelRanges() {
+    try {
+      const tables = await invoke<string[]>("register_excel_ranges");
+      setFileMeta((current) => (current ? { ...current, tables } : current));
+      toast.success(t("tablesRegistered", { count: tables.length }));
+    } catch (error) {
+      toast.error(errorText(error));
+    }
+  }
+
+  async function selectVersion(version: string) {
+    if (!version) return;
+    try {
+      const response = await invoke<FileMetadataResponse>("select_table_version", {
+        version,
       });
       setFileMeta(response);
       setResult(null);
       setQueryMs(null);
       setSortState(null);
     } catch (error) {
-      toast.error(String(error));
+      toast.error(errorText(error));
     }
   }
 
@@ -302,12 +996,27 @@ function App() {
             "csv",
             "tsv",
             "txt",
+            "dat",
+            "fwf",
             "jsonl",
             "ndjson",
             "json",
             "arrow",
             "feather",
             "ipc",
+            "xlsx",
+            "xlsm",
+            "xls",
+            "xlsb",
+            "ods",
+            "avro",
+            "orc",
+            "zip",
+            "tar",
+            "tgz",
+            "db",
+            "sqlite",
+            "sqlite3",
             "gz",
           ],
         },
@@ -318,6 +1027,79 @@ function App() {
     }
   }
 
+  function formatLayout(layout?: FixedWidthOptions | null) {
+    if (!layout) return "";
+    return layout.columns
+      .map((column) =>
+        [column.name, column.start, column.width, column.dtype]
+          .filter((part) => part !== null && part !== undefined && part !== "")
+          .join(":"),
+      )
+      .join(", ");
+  }
+
+  async function editFixedWidthLayout() {
+    const spec = window.prompt(t("fixedWidthPrompt"), formatLayout(fileMeta?.fixed_width));
+    if (spec === null) return;
+    // An empty spec asks the backend to infer the layout again.
+    const columns = spec
+      .split(",")
+      .map((part) => part.trim())
+      .filter(Boolean)
+      .map((part) => {
+        const [name, start, width, dtype] = part.split(":").map((value) => value.trim());
+        return { name, start: Number(start), width: Number(width), dtype: dtype || null };
+      });
+    if (columns.some((column) => !column.name || isNaN(column.start) || isNaN(column.width))) {
+      toast.error(t("fixedWidthInvalid"));
+      return;
+    }
+    try {
+      const response = await invoke<FileMetadataResponse>("set_fixed_width_layout", {
+        options: {
+          columns,
+          skip_rows: columns.length > 0 ? (fileMeta?.fixed_width?.skip_rows ?? 1) : 0,
+        },
+      });
+      setFileMeta(response);
+      setResult(null);
+      setQueryMs(null);
+      setSortState(null);
+    } catch (error) {
+      toast.error(errorText(error));
+    }
+  }
+
+  async function editS3Settings() {
+    try {
+      const current = await invoke<S3Settings>("get_s3_settings");
+      const endpoint = window.prompt(t("s3EndpointPrompt"), current.endpoint ?? "");
+      if (endpoint === null) return;
+      const region = window.prompt(t("s3RegionPrompt"), current.region ?? "");
+      if (region === null) return;
+      const profile = window.prompt(t("s3ProfilePrompt"), current.profile ?? "");
+      if (profile === null) return;
+      // Empty fields fall back to the AWS_* environment and ~/.aws.
+      const settings: S3Settings = {
+        endpoint: endpoint.trim() || null,
+        region: region.trim() || null,
+        profile: profile.trim() || null,
+        allow_http: /^http:\/\//i.test(endpoint.trim()),
+      };
+      await invoke("set_s3_settings", { settings });
+      toast.success(t("s3Saved"));
+    } catch (error) {
+      toast.error(errorText(error));
+    }
+  }
+
+  function openUri() {
+    const uri = window.prompt(t("openUriPrompt"));
+    if (uri && uri.trim()) {
+      loadFile(uri.trim());
+    }
+  }
+
   async function loadSample(fileName: string) {
     try {
       const path = await invoke<string>("resolve_sample_path", {
@@ -325,7 +1107,7 @@ function App() {
       });
       await loadFile(path);
     } catch (error) {
-      toast.error(String(error));
+      toast.error(errorText(error));
     }
   }
 
@@ -339,6 +1121,12 @@ function App() {
           <p className="subtitle">{t("subtitle")}</p>
         </div>
         <div className="header-actions">
+          <button className="ghost-button" onClick={pickWorkspace}>
+            {t("openWorkspace")}
+          </button>
+          <button className="ghost-button" onClick={saveWorkspace}>
+            {t("saveWorkspace")}
+          </button>
           <button
             className="ghost-button"
             onClick={() =>
@@ -365,9 +1153,17 @@ function App() {
           <div className="panel">
             <div className="panel-header">
               <h2>{t("fileInfo")}</h2>
-              <button className="ghost-button" onClick={pickFile}>
-                {t("openFile")}
-              </button>
+              <div>
+                <button className="ghost-button" onClick={openUri}>
+                  {t("openUri")}
+                </button>
+                <button className="ghost-button" onClick={editS3Settings}>
+                  {t("s3Settings")}
+                </button>
+                <button className="ghost-button" onClick={pickFile}>
+                  {t("openFile")}
+                </button>
+              </div>
             </div>
             {fileMeta ? (
               <div className="meta-grid">
@@ -387,9 +1183,32 @@ function App() {
                   <span>{t("filePath")}</span>
                   <strong title={fileMeta.file_path}>{fileMeta.file_path}</strong>
                 </div>
+                {fileMeta.storage && (
+                  <div>
+                    <span>
+                      {fileMeta.storage.block_kind === "stripe"
+                        ? t("stripes")
+                        : t("rowGroups")}
+                    </span>
+                    <strong
+                      title={fileMeta.storage.blocks
+                        .map((block) => block.row_count.toLocaleString())
+                        .join(" / ")}
+                    >
+                      {fileMeta.storage.blocks.length} ·{" "}
+                      {fileMeta.storage.compression.join(", ")}
+                    </strong>
+                  </div>
+                )}
                 {fileMeta.sheets.length > 0 && (
                   <div className="sheet-row">
-                    <span>{t("sheet")}</span>
+                    <span>
+                      {fileMeta.file_kind === "sqlite"
+                        ? t("table")
+                        : fileMeta.file_kind === "archive"
+                          ? t("member")
+                          : t("sheet")}
+                    </span>
                     <select
                       value={fileMeta.active_sheet ?? fileMeta.sheets[0]}
                       onChange={(event) => selectSheet(event.target.value)}
@@ -399,6 +1218,99 @@ function App() {
                           {sheet}
                         </option>
                       ))}
+                      {fileMeta.ranges.length > 0 && (
+                        <optgroup label={t("ranges")}>
+                          {fileMeta.ranges.map((range) => (
+                            <option key={range.name} value={range.name}>
+                              {range.name} ·{" "}
+                              {range.kind === "table" ? t("excelTable") : t("definedName")} (
+                              {range.sheet})
+                            </option>
+                          ))}
+                        </optgroup>
+                      )}
+                    </select>
+                  </div>
+                )}
+                {WATCHABLE_KINDS.includes(fileMeta.file_kind) &&
+                  !/^(s3|https?):\/\//i.test(fileMeta.file_path) && (
+                    <div className="sheet-row">
+                      <span>{t("liveMode")}</span>
+                      <select
+                        value={liveMode}
+                        onChange={(event) => changeLiveMode(event.target.value)}
+                      >
+                        {LIVE_MODES.map((mode) => (
+                          <option key={mode} value={mode}>
+                            {mode === "off"
+                              ? t("liveOff")
+                              : mode === "watch"
+                                ? t("liveWatch")
+                                : t("liveTail", { seconds: Number(mode) / 1000 })}
+                          </option>
+                        ))}
+                      </select>
+                    </div>
+                  )}
+                {fileMeta.file_kind === "archive" && (
+                  <div>
+                    <span>{t("tables")}</span>
+                    {fileMeta.tables.length > 0 ? (
+                      <strong title={fileMeta.tables.join(", ")}>
+                        source, {fileMeta.tables.join(", ")}
+                      </strong>
+                    ) : (
+                      <button className="ghost-button" onClick={registerArchiveMembers}>
+                        {t("registerAllMembers")}
+                      </button>
+                    )}
+                  </div>
+                )}
+                {fileMeta.file_kind === "excel" && fileMeta.ranges.length > 0 && (
+                  <div>
+                    <span>{t("tables")}</span>
+                    {fileMeta.tables.length > 0 ? (
+                      <strong title={fileMeta.tables.join(", ")}>
+                        source, {fileMeta.tables.join(", ")}
+                      </strong>
+                    ) : (
+                      <button className="ghost-button" onClick={registerExcelRanges}>
+                        {t("registerAllRanges")}
+                      </button>
+                    )}
+                  </div>
+                )}
+                {(fileMeta.file_kind === "fixed_width" ||
+                  /\.txt(\.gz)?$/i.test(fileMeta.file_name)) && (
+                  <div>
+                    <span>{t("fixedWidthLayout")}</span>
+                    <button
+                      className="ghost-button"
+                      title={formatLayout(fileMeta.fixed_width)}
+                      onClick={editFixedWidthLayout}
+                    >
+                      {fileMeta.fixed_width
+                        ? t("fixedWidthColumns", { count: fileMeta.fixed_width.columns.length })
+                        : t("fixedWidthUse")}
+                    </button>
+                  </div>
+                )}
+                {fileMeta.versions.length > 0 && (
+                  <div className="sheet-row">
+                    <span>{t("version")}</span>
+                    <select
+                      value={fileMeta.active_version ?? fileMeta.versions[0].id}
+                      onChange={(event) => selectVersion(event.target.value)}
+                    >
+                      {fileMeta.versions.map((version) => (
+                        <option key={version.id} value={version.id}>
+                          {version.id}
+                          {version.timestamp_ms
+                            ? ` · ${new Date(version.timestamp_ms).toLocaleString()}`
+                            : ""}
+                          {version.operation ? ` · ${version.operation}` : ""}
+                        </option>
+                      ))}
                     </select>
                   </div>
                 )}
@@ -435,6 +1347,220 @@ function App() {
               <p className="muted">{t("schemaHint")}</p>
             )}
           </div>
+          <div className="panel">
+            <div className="panel-header">
+              <h2>{t("recentFiles")}</h2>
+              <button
+                className="ghost-button"
+                onClick={pruneRecentFiles}
+                disabled={!recentFiles.length}
+              >
+                {t("recentPrune")}
+              </button>
+            </div>
+            {recentFiles.length > 0 ? (
+              <div className="history-list">
+                {recentFiles.map((file) => (
+                  <div
+                    key={file.path}
+                    className={clsx("history-row", file.missing && "history-error")}
+                  >
+                    <button
+                      className="history-sql"
+                      title={file.missing ? t("recentMissing") : file.path}
+                      disabled={file.missing || isLoadingFile}
+                      onClick={() => loadFile(file.path)}
+                    >
+                      {file.path.split(/[\\/]/).pop() || file.path}
+                    </button>
+                    <div className="history-meta">
+                      <em title={file.path}>
+                        {file.kind} · {formatBytes(file.size)} ·{" "}
+                        {new Date(file.last_opened_ms).toLocaleString()}
+                      </em>
+                      <button
+                        className="icon-button"
+                        title={file.pinned ? t("unpin") : t("pin")}
+                        onClick={() => pinRecentFile(file)}
+                      >
+                        {file.pinned ? "★" : "☆"}
+                      </button>
+                    </div>
+                  </div>
+                ))}
+              </div>
+            ) : (
+              <p className="muted">{t("recentEmpty")}</p>
+            )}
+          </div>
+          <div className="panel">
+            <div className="panel-header">
+              <h2>{t("savedQueries")}</h2>
+              <div className="panel-actions">
+                <button className="ghost-button" onClick={saveCurrentQuery}>
+                  {t("savedSave")}
+                </button>
+                <button className="ghost-button" onClick={importSavedQueries}>
+                  {t("savedImport")}
+                </button>
+                <button
+                  className="ghost-button"
+                  onClick={exportSavedQueries}
+                  disabled={!savedQueries.length}
+                >
+                  {t("savedExport")}
+                </button>
+              </div>
+            </div>
+            {savedQueries.length > 0 ? (
+              <div className="history-list">
+                {savedQueries.map((query) => (
+                  <div key={query.id} className="history-row">
+                    <button
+                      className="history-sql"
+                      title={[query.description, query.sql].filter(Boolean).join("\n\n")}
+                      onClick={() => setSql(query.sql)}
+                    >
+                      {query.name}
+                    </button>
+                    <div className="history-meta">
+                      <em>
+                        {query.tags.map((tag) => `#${tag}`).join(" ")}
+                        {query.params.length > 0
+                          ? ` {${query.params.map((param) => param.name).join(", ")}}`
+                          : ""}
+                      </em>
+                      <button
+                        className="icon-button"
+                        title={t("runQuery")}
+                        disabled={!fileMeta || isRunningQuery}
+                        onClick={() => runSavedQuery(query)}
+                      >
+                        ▶
+                      </button>
+                      <button
+                        className="icon-button"
+                        title={t("delete")}
+                        onClick={() => deleteSavedQuery(query)}
+                      >
+                        ✕
+                      </button>
+                    </div>
+                  </div>
+                ))}
+              </div>
+            ) : (
+              <p className="muted">{t("savedEmpty", { placeholder: "{{param}}" })}</p>
+            )}
+          </div>
+
+          <div className="panel">
+            <h2>{t("history")}</h2>
+            <input
+              className="history-search"
+              value={historySearch}
+              placeholder={t("historySearch")}
+              onChange={(event) => setHistorySearch(event.target.value)}
+            />
+            {history.length > 0 ? (
+              <div className="history-list">
+                {history.map((entry) => (
+                  <div
+                    key={entry.id}
+                    className={clsx("history-row", entry.error && "history-error")}
+                  >
+                    <button
+                      className="history-sql"
+                      title={entry.error ?? entry.sql}
+                      onClick={() => setSql(entry.sql)}
+                      onDoubleClick={() => {
+                        setSql(entry.sql);
+                        runQuery(entry.sql);
+                      }}
+                    >
+                      {entry.sql}
+                    </button>
+                    <div className="history-meta">
+                      <em title={entry.files.join("\n")}>
+                        {new Date(entry.executed_at_ms).toLocaleString()} · {entry.duration_ms} ms
+                        {entry.row_count != null
+                          ? ` · ${entry.row_count.toLocaleString()} ${t("rows")}`
+                          : ` · ${t("historyFailed")}`}
+                      </em>
+                      <button
+                        className="icon-button"
+                        title={entry.pinned ? t("unpin") : t("pin")}
+                        onClick={() => pinHistoryEntry(entry)}
+                      >
+                        {entry.pinned ? "★" : "☆"}
+                      </button>
+                      <button
+                        className="icon-button"
+                        title={t("delete")}
+                        onClick={() => deleteHistoryEntry(entry)}
+                      >
+                        ✕
+                      </button>
+                    </div>
+                  </div>
+                ))}
+              </div>
+            ) : (
+              <p className="muted">{t("historyEmpty")}</p>
+            )}
+          </div>
+
+          <div className="panel">
+            <div className="panel-header">
+              <h2>{t("apiServer")}</h2>
+              <button className="ghost-button" onClick={toggleApiServer}>
+                {apiStatus.running ? t("apiStop") : t("apiStart")}
+              </button>
+            </div>
+            {apiStatus.running ? (
+              <div className="meta-grid">
+                <div>
+                  <span>{t("apiUrl")}</span>
+                  <strong>{apiStatus.url}</strong>
+                </div>
+                <div>
+                  <span>{t("apiToken")}</span>
+                  <button className="ghost-button" onClick={copyApiToken}>
+                    {t("apiCopyToken")}
+                  </button>
+                </div>
+              </div>
+            ) : (
+              <p className="muted">{t("apiOff")}</p>
+            )}
+            {apiQueries.length > 0 && (
+              <div className="history-list">
+                {apiQueries.map((entry) => (
+                  <div
+                    key={entry.id}
+                    className={clsx("history-row", entry.error && "history-error")}
+                  >
+                    <button
+                      className="history-sql"
+                      title={entry.error ?? entry.sql}
+                      onClick={() => setSql(entry.sql)}
+                    >
+                      {entry.sql}
+                    </button>
+                    <div className="history-meta">
+                      <em title={entry.client ?? undefined}>
+                        {new Date(entry.started_ms).toLocaleString()} · {entry.format} ·{" "}
+                        {entry.duration_ms} ms
+                        {entry.row_count != null
+                          ? ` · ${entry.row_count.toLocaleString()} ${t("rows")}`
+                          : ` · ${t("historyFailed")}`}
+                      </em>
+                    </div>
+                  </div>
+                ))}
+              </div>
+            )}
+          </div>
         </section>
 
         <section className="workspace">
@@ -449,6 +1575,9 @@ function App() {
                 >
                   {isRunningQuery ? t("running") : t("runQuery")}
                 </button>
+                <button className="ghost-button" onClick={() => formatQuery()}>
+                  {t("formatSql")}
+                </button>
                 <button
                   className="ghost-button"
                   onClick={() => exportQuery("csv")}
@@ -463,6 +1592,20 @@ function App() {
                 >
                   {t("exportXlsx")}
                 </button>
+                <button
+                  className="ghost-button"
+                  onClick={exportSqlite}
+                  disabled={!fileMeta}
+                >
+                  {t("exportSqlite")}
+                </button>
+                <button
+                  className="ghost-button"
+                  onClick={exportPartitioned}
+                  disabled={!fileMeta}
+                >
+                  {t("exportPartitioned")}
+                </button>
               </div>
             </div>
             <div className="editor-shell">
@@ -471,13 +1614,52 @@ function App() {
                 language="sql"
                 theme={theme === "dark" ? "vs-dark" : "light"}
                 value={sql}
-                onChange={(value) => setSql(value ?? "")}
+                onChange={(value) => {
+                  setSql(value ?? "");
+                  markSqlError(null);
+                }}
                 options={{
                   fontSize: 14,
                   minimap: { enabled: false },
                   scrollBeyondLastLine: false,
                 }}
                 onMount={(editor, monaco) => {
+                  editorRef.current = editor;
+                  monacoRef.current = monaco;
+                  const kinds = {
+                    table: monaco.languages.CompletionItemKind.Class,
+                    column: monaco.languages.CompletionItemKind.Field,
+                    function: monaco.languages.CompletionItemKind.Function,
+                    keyword: monaco.languages.CompletionItemKind.Keyword,
+                  };
+                  completionRef.current?.dispose();
+                  completionRef.current = monaco.languages.registerCompletionItemProvider("sql", {
+                    triggerCharacters: ["."],
+                    provideCompletionItems: async (model, position) => {
+                      const word = model.getWordUntilPosition(position);
+                      const range = {
+                        startLineNumber: position.lineNumber,
+                        endLineNumber: position.lineNumber,
+                        startColumn: word.startColumn,
+                        endColumn: word.endColumn,
+                      };
+                      const items = await invoke<Completion[]>("complete_sql", {
+                        sql: model.getValue(),
+                        cursorOffset: model.getOffsetAt(position),
+                      }).catch(() => [] as Completion[]);
+                      return {
+                        suggestions: items.map((item, index) => ({
+                          label: item.label,
+                          kind: kinds[item.kind],
+                          detail: item.detail ?? undefined,
+                          insertText: item.insert_text,
+                          range,
+                          // Keep the backend's ranking.
+                          sortText: String(index).padStart(4, "0"),
+                        })),
+                      };
+                    },
+                  });
                   editor.addAction({
                     id: "run-query",
                     label: "Run Query",
@@ -486,6 +1668,14 @@ function App() {
                     ],
                     run: () => runQuery(),
                   });
+                  editor.addAction({
+                    id: "format-sql",
+                    label: "Format SQL",
+                    keybindings: [
+                      monaco.KeyMod.Shift | monaco.KeyMod.Alt | monaco.KeyCode.KeyF,
+                    ],
+                    run: (current) => formatQuery(current.getValue()),
+                  });
                 }}
               />
             </div>